@@ -0,0 +1,271 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum EntryKind {
+    Directory,
+    File,
+    Executable,
+    Symlink,
+}
+
+pub struct Entry {
+    pub name: String,
+    pub kind: EntryKind,
+}
+
+/// Patterns from the `.gitignore` files between a directory and the root of
+/// its repository. Only the common subset is understood: `*` and `?`
+/// wildcards, a leading `/` to anchor, a trailing `/` for directories and
+/// `!` to take an earlier pattern back.
+#[derive(Default)]
+pub struct Ignore {
+    /// The directory each pattern is relative to, the pattern and whether it
+    /// is negated.
+    patterns: Vec<(PathBuf, String, bool)>,
+}
+
+impl Ignore {
+    pub fn load(dir: &Path) -> Self {
+        let mut files = Vec::new();
+        for ancestor in dir.ancestors() {
+            if let Ok(contents) = fs::read_to_string(ancestor.join(".gitignore")) {
+                files.push((ancestor.to_path_buf(), contents));
+            }
+            if ancestor.join(".git").exists() {
+                break;
+            }
+        }
+        let mut ignore = Self::default();
+        // Patterns further down override those above them.
+        for (base, contents) in files.iter().rev() {
+            ignore.add(base, contents);
+        }
+        ignore
+    }
+    pub fn add(&mut self, base: &Path, contents: &str) {
+        for line in contents.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (pattern, negated) = match line.strip_prefix('!') {
+                Some(pattern) => (pattern, true),
+                None => (line, false),
+            };
+            self.patterns.push((base.to_path_buf(), pattern.to_string(), negated));
+        }
+    }
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for (base, pattern, negated) in &self.patterns {
+            let relative = match path.strip_prefix(base) {
+                Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+                Err(_) => continue,
+            };
+            let (pattern, dir_only) = match pattern.strip_suffix('/') {
+                Some(pattern) => (pattern, true),
+                None => (pattern.as_str(), false),
+            };
+            if dir_only && !is_dir {
+                continue;
+            }
+            let matches = if pattern.contains('/') {
+                glob_match(pattern.trim_start_matches('/'), &relative)
+            } else {
+                let name = relative.rsplit('/').next().unwrap_or_default();
+                glob_match(pattern, name)
+            };
+            if matches {
+                ignored = !negated;
+            }
+        }
+        ignored
+    }
+}
+
+/// Matches `text` against a pattern where `*` stands for any run of
+/// characters except `/` and `?` for a single one.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == text[t] || (c == '?' && text[t] != '/') => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) if text[matched] != '/' => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                _ => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A directory listing shown in place of a document.
+pub struct FileBrowser {
+    pub dir: PathBuf,
+    pub entries: Vec<Entry>,
+    pub selected: usize,
+    pub show_hidden: bool,
+}
+
+impl FileBrowser {
+    pub fn open(dir: &Path) -> Result<Self, io::Error> {
+        let mut browser = Self {
+            dir: fs::canonicalize(dir)?,
+            entries: Vec::new(),
+            selected: 0,
+            show_hidden: false,
+        };
+        browser.refresh()?;
+        Ok(browser)
+    }
+    /// Reads the directory again, keeping the selection where possible.
+    pub fn refresh(&mut self) -> Result<(), io::Error> {
+        let selected = self.selection().map(|entry| entry.name.clone());
+        let ignore = Ignore::load(&self.dir);
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !self.show_hidden && name.starts_with('.') {
+                continue;
+            }
+            let kind = kind(&entry.path());
+            if ignore.is_ignored(&entry.path(), kind == EntryKind::Directory) {
+                continue;
+            }
+            entries.push(Entry { name, kind });
+        }
+        entries.sort_by(|a, b| {
+            (b.kind == EntryKind::Directory)
+                .cmp(&(a.kind == EntryKind::Directory))
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        if self.dir.parent().is_some() {
+            entries.insert(
+                0,
+                Entry {
+                    name: "..".to_string(),
+                    kind: EntryKind::Directory,
+                },
+            );
+        }
+        self.entries = entries;
+        self.selected = selected
+            .and_then(|name| self.entries.iter().position(|entry| entry.name == name))
+            .unwrap_or(0)
+            .min(self.entries.len().saturating_sub(1));
+        Ok(())
+    }
+    pub fn selection(&self) -> Option<&Entry> {
+        self.entries.get(self.selected)
+    }
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        self.selection().map(|entry| self.dir.join(&entry.name))
+    }
+    /// The first entry shown when `height` rows fit on the screen.
+    pub fn first_visible(&self, height: usize) -> usize {
+        self.selected.saturating_sub(height.saturating_sub(1))
+    }
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+    pub fn select(&mut self, name: &str) {
+        if let Some(index) = self.entries.iter().position(|entry| entry.name == name) {
+            self.selected = index;
+        }
+    }
+    /// Moves into `dir`, selecting the directory we came from when going up.
+    pub fn change_dir(&mut self, dir: &Path) -> Result<(), io::Error> {
+        let previous = self.dir.file_name().map(|name| name.to_string_lossy().to_string());
+        let dir = fs::canonicalize(dir)?;
+        let going_up = self.dir.parent() == Some(dir.as_path());
+        self.dir = dir;
+        self.selected = 0;
+        self.refresh()?;
+        if let (true, Some(previous)) = (going_up, previous) {
+            self.select(&previous);
+        }
+        Ok(())
+    }
+    pub fn toggle_hidden(&mut self) -> Result<(), io::Error> {
+        self.show_hidden = !self.show_hidden;
+        self.refresh()
+    }
+}
+
+fn kind(path: &Path) -> EntryKind {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return EntryKind::File,
+    };
+    if metadata.file_type().is_symlink() {
+        return if path.is_dir() {
+            EntryKind::Directory
+        } else {
+            EntryKind::Symlink
+        };
+    }
+    if metadata.is_dir() {
+        return EntryKind::Directory;
+    }
+    if is_executable(&metadata) {
+        EntryKind::Executable
+    } else {
+        EntryKind::File
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_ignore() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "src/main.rs"));
+        assert!(glob_match("ta?get", "target"));
+
+        let base = Path::new("/repo");
+        let mut ignore = Ignore::default();
+        ignore.add(base, "# build output\ntarget/\n*.swp\n!keep.swp\n/docs/*.html\n");
+        assert!(ignore.is_ignored(Path::new("/repo/target"), true));
+        assert!(!ignore.is_ignored(Path::new("/repo/target"), false));
+        assert!(ignore.is_ignored(Path::new("/repo/src/.main.rs.swp"), false));
+        assert!(!ignore.is_ignored(Path::new("/repo/keep.swp"), false));
+        assert!(ignore.is_ignored(Path::new("/repo/docs/index.html"), false));
+        assert!(!ignore.is_ignored(Path::new("/repo/src/docs/index.html"), false));
+        assert!(!ignore.is_ignored(Path::new("/elsewhere/x.swp"), false));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::goto::Target;
+use crate::uwu;
+use crate::FileType;
+use std::path::{Path, PathBuf};
+
+pub const HELP: &str = "\
+kyun - the wowst text editow in existence (｡♥‿♥｡)
+
+Usage: kyun [OPTIONS] [+N] [FILE[:LINE[:COLUMN]]]...
+       kyun --uwuify [--skip-code] [--keep-line-endings] [--intensity LEVEL] [FILE]
+       kyun --deuwuify [FILE]
+
+Awguments:
+  FILE             fiwes to open, each in its own buffew; - weads stdin
+                   and a diwectowy opens the fiwe bwowsew
+  +N               stawt the next fiwe at wine N
+
+Options:
+  -R, --readonly   open evewything wead-onwy
+  -t, --filetype   highwight as wust, uwu++ ow text
+      --no-uwu     type pwain text, no uwu
+      --intensity  how much uwu: wight, medium ow maximum
+  -c, --config     wead this config fiwe instead of the usuaw one
+      --uwuify     wwite FILE (ow stdin) to stdout the way typing it
+                   into kyun wouwd, without stawting the editow
+      --skip-code  with --uwuify, weave Mawkdown code awone
+      --keep-line-endings
+                   with --uwuify, keep \\r\\n instead of wwiting \\n
+      --deuwuify   wwite FILE (ow stdin) back in pwain Engwish, wisting
+                   the wowds that couwd be mowe than one on stdeww
+  -h, --help       show this hewp
+  -V, --version    show the vewsion
+";
+
+/// What kyun was asked to do.
+#[derive(PartialEq, Debug)]
+pub enum Action {
+    Edit(Args),
+    /// Uwuify a file, or stdin for `None`, to stdout. The intensity and
+    /// config file are resolved like the editor's, so both type alike.
+    Uwuify {
+        file: Option<String>,
+        options: uwu::Options,
+        intensity: Option<uwu::Intensity>,
+        config: Option<PathBuf>,
+    },
+    /// Turn a file, or stdin for `None`, back into plain text on stdout.
+    Deuwuify { file: Option<String> },
+    Help,
+    Version,
+}
+
+#[derive(PartialEq, Debug, Default)]
+pub struct Args {
+    pub files: Vec<FileArg>,
+    pub file_type: Option<String>,
+    pub no_uwu: bool,
+    pub config: Option<PathBuf>,
+    pub read_only: bool,
+    pub intensity: Option<uwu::Intensity>,
+}
+
+/// A file to open and where to put the cursor in it.
+#[derive(PartialEq, Debug)]
+pub struct FileArg {
+    pub name: String,
+    pub target: Option<Target>,
+}
+
+/// Parses the arguments after the program name.
+pub fn parse<I>(args: I) -> Result<Action, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut parsed = Args::default();
+    let mut target = None;
+    let mut only_files = false;
+    let mut batch = false;
+    let mut plain = false;
+    let mut options = uwu::Options::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if only_files || arg == "-" || !(arg.starts_with('-') || arg.starts_with('+')) {
+            let file = match target.take() {
+                Some(target) => FileArg {
+                    name: arg,
+                    target: Some(target),
+                },
+                None => file_arg(&arg),
+            };
+            parsed.files.push(file);
+            continue;
+        }
+        // Long options take their value after `=` or as the next argument.
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |what: &str| {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} needs {}", flag, what))
+        };
+        match flag {
+            "--" => only_files = true,
+            "-h" | "--help" => return Ok(Action::Help),
+            "-V" | "--version" => return Ok(Action::Version),
+            "-R" | "--readonly" => parsed.read_only = true,
+            "--no-uwu" => parsed.no_uwu = true,
+            "--uwuify" => batch = true,
+            "--deuwuify" => plain = true,
+            "--skip-code" => options.skip_code = true,
+            "--keep-line-endings" => options.keep_line_endings = true,
+            "--intensity" => {
+                let name = value("a wevew")?;
+                let intensity = uwu::Intensity::from_name(&name)
+                    .ok_or_else(|| format!("unknown intensity {}", name))?;
+                parsed.intensity = Some(intensity);
+            }
+            "-t" | "--filetype" => {
+                let name = value("a fiwe type")?;
+                if FileType::from_name(&name).is_none() {
+                    return Err(format!("unknown fiwe type {}", name));
+                }
+                parsed.file_type = Some(name);
+            }
+            "-c" | "--config" => parsed.config = Some(PathBuf::from(value("a fiwe")?)),
+            _ if flag.starts_with('+') => {
+                let line = flag[1..]
+                    .parse()
+                    .map_err(|_| format!("{} is not a wine numbew", flag))?;
+                target = Some(Target::Line { line, column: None });
+            }
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+    if batch && plain {
+        return Err("--uwuify and --deuwuify don't go togethew".to_string());
+    }
+    if batch || plain {
+        if parsed.files.len() > 1 {
+            return Err("--uwuify and --deuwuify take one fiwe at a time".to_string());
+        }
+        let file = parsed.files.pop().map(|file| file.name).filter(|name| name != "-");
+        if plain && options == uwu::Options::default() {
+            return Ok(Action::Deuwuify { file });
+        }
+        if batch {
+            return Ok(Action::Uwuify {
+                file,
+                options,
+                intensity: parsed.intensity,
+                config: parsed.config,
+            });
+        }
+    }
+    if options != uwu::Options::default() {
+        return Err("--skip-code and --keep-line-endings go with --uwuify".to_string());
+    }
+    if let Some(target) = target {
+        match parsed.files.last_mut() {
+            Some(file) => file.target = Some(target),
+            None => return Err("+N needs a fiwe to go with it".to_string()),
+        }
+    }
+    Ok(Action::Edit(parsed))
+}
+
+/// Splits `name:line[:column]`, unless a file with the whole name exists.
+fn file_arg(arg: &str) -> FileArg {
+    let number = |part: &str| part.parse::<usize>().ok();
+    let plain = FileArg {
+        name: arg.to_string(),
+        target: None,
+    };
+    if Path::new(arg).exists() {
+        return plain;
+    }
+    let parts: Vec<&str> = arg.rsplitn(3, ':').collect();
+    if let [column, line, name] = parts.as_slice() {
+        if let (Some(line), Some(column), false) = (number(line), number(column), name.is_empty()) {
+            return FileArg {
+                name: name.to_string(),
+                target: Some(Target::Line {
+                    line,
+                    column: Some(column),
+                }),
+            };
+        }
+    }
+    match arg.rsplit_once(':') {
+        Some((name, line)) if !name.is_empty() && number(line).is_some() => FileArg {
+            name: name.to_string(),
+            target: number(line).map(|line| Target::Line { line, column: None }),
+        },
+        _ => plain,
+    }
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Action, String> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = args(&["-R", "+3", "notes.txt", "src/main.rs:12:5", "--filetype=rust"]);
+        assert_eq!(
+            parsed,
+            Ok(Action::Edit(Args {
+                files: vec![
+                    FileArg {
+                        name: "notes.txt".to_string(),
+                        target: Some(Target::Line { line: 3, column: None }),
+                    },
+                    FileArg {
+                        name: "src/main.rs".to_string(),
+                        target: Some(Target::Line { line: 12, column: Some(5) }),
+                    },
+                ],
+                file_type: Some("rust".to_string()),
+                read_only: true,
+                ..Args::default()
+            }))
+        );
+        assert_eq!(args(&["-", "--help"]), Ok(Action::Help));
+        assert!(args(&["--nyaa"]).is_err());
+        assert!(args(&["--filetype", "cobol"]).is_err());
+        assert!(args(&["--config"]).is_err());
+        assert!(args(&["+3"]).is_err());
+        assert_eq!(
+            args(&["--uwuify", "--skip-code", "--intensity=max", "-c", "kyun.conf", "-"]),
+            Ok(Action::Uwuify {
+                file: None,
+                options: uwu::Options {
+                    skip_code: true,
+                    ..uwu::Options::default()
+                },
+                intensity: Some(uwu::Intensity::Maximum),
+                config: Some(PathBuf::from("kyun.conf")),
+            })
+        );
+        assert!(args(&["--skip-code", "notes.md"]).is_err());
+        assert!(args(&["--intensity", "extreme"]).is_err());
+        assert_eq!(
+            args(&["--deuwuify", "notes.md"]),
+            Ok(Action::Deuwuify {
+                file: Some("notes.md".to_string())
+            })
+        );
+        assert!(args(&["--deuwuify", "--skip-code"]).is_err());
+        match args(&["--", "--weird-name", "a:b:2"]) {
+            Ok(Action::Edit(parsed)) => {
+                assert_eq!(parsed.files[0].name, "--weird-name");
+                assert_eq!(parsed.files[1].name, "a:b");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::goto::{self, Target};
+
+/// A command typed at the `:` command line.
+#[derive(PartialEq, Debug)]
+pub enum Command {
+    Write(Option<String>),
+    Edit(String),
+    Quit { force: bool },
+    Goto(Target),
+    Set { option: String, value: String },
+    FileType(String),
+    Uwuify,
+    Deuwuify,
+    Browse(Option<String>),
+    NextBuffer,
+    PreviousBuffer,
+}
+
+pub const COMMANDS: [&str; 12] = [
+    "w", "e", "q", "q!", "goto", "set", "filetype", "uwuify", "deuwuify", "browse", "bn", "bp",
+];
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let line = line.trim().trim_start_matches(':');
+    let (name, argument) = match line.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (line, ""),
+    };
+    let required = |what: &str| {
+        if argument.is_empty() {
+            Err(format!("{} needs {}", name, what))
+        } else {
+            Ok(argument.to_string())
+        }
+    };
+    match name {
+        "w" | "write" => Ok(Command::Write(if argument.is_empty() {
+            None
+        } else {
+            Some(argument.to_string())
+        })),
+        "e" | "edit" => Ok(Command::Edit(required("a fiwe name")?)),
+        "q" | "quit" => Ok(Command::Quit { force: false }),
+        "q!" | "quit!" => Ok(Command::Quit { force: true }),
+        "goto" => goto::parse(&required("a wine numbew")?).map(Command::Goto),
+        "set" => {
+            let setting = required("option=value")?;
+            let (option, value) = setting
+                .split_once('=')
+                .ok_or_else(|| format!("{} is not option=value", setting))?;
+            Ok(Command::Set {
+                option: option.trim().to_string(),
+                value: value.trim().to_string(),
+            })
+        }
+        "filetype" | "ft" => Ok(Command::FileType(required("a fiwe type")?)),
+        "uwuify" => Ok(Command::Uwuify),
+        "deuwuify" | "unuwu" => Ok(Command::Deuwuify),
+        "bn" | "bnext" => Ok(Command::NextBuffer),
+        "bp" | "bprevious" => Ok(Command::PreviousBuffer),
+        "browse" | "ex" => Ok(Command::Browse(if argument.is_empty() {
+            None
+        } else {
+            Some(argument.to_string())
+        })),
+        "" => Err("No command given".to_string()),
+        _ => Err(format!("Unknown command: {}", name)),
+    }
+}
+
+/// Command names starting with `prefix`.
+pub fn complete(prefix: &str) -> Vec<&'static str> {
+    COMMANDS
+        .iter()
+        .copied()
+        .filter(|command| command.starts_with(prefix))
+        .collect()
+}
+
+/// The longest string all `candidates` start with.
+pub fn common_prefix(candidates: &[&str]) -> String {
+    let mut prefix = candidates.first().map_or("", |first| first).to_string();
+    for candidate in candidates {
+        while !candidate.starts_with(&prefix) {
+            prefix.pop();
+        }
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("w"), Ok(Command::Write(None)));
+        assert_eq!(parse(":w notes.txt"), Ok(Command::Write(Some("notes.txt".to_string()))));
+        assert_eq!(parse("q!"), Ok(Command::Quit { force: true }));
+        assert_eq!(
+            parse("goto 12"),
+            Ok(Command::Goto(Target::Line { line: 12, column: None }))
+        );
+        assert!(parse("goto twelve").is_err());
+        assert_eq!(
+            parse("set tabwidth = 4"),
+            Ok(Command::Set {
+                option: "tabwidth".to_string(),
+                value: "4".to_string()
+            })
+        );
+        assert!(parse("set tabwidth").is_err());
+        assert_eq!(parse("filetype rust"), Ok(Command::FileType("rust".to_string())));
+        assert_eq!(parse("browse"), Ok(Command::Browse(None)));
+        assert!(parse("e").is_err());
+        assert!(parse("nyaa").is_err());
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(complete("f"), vec!["filetype"]);
+        assert_eq!(complete("d"), vec!["deuwuify"]);
+        assert_eq!(complete("q"), vec!["q", "q!"]);
+        assert_eq!(common_prefix(&complete("q")), "q");
+        assert_eq!(common_prefix(&complete("go")), "goto");
+    }
+}
@@ -0,0 +1,80 @@
+use crate::Document;
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The most completions offered at once.
+const MAX_COMPLETIONS: usize = 50;
+
+/// Words that complete `prefix`, best first. Words in `document` come first,
+/// closest to line `y` and then most used; then words only found in
+/// `others`, then `keywords`.
+pub fn candidates(
+    prefix: &str,
+    document: &Document,
+    y: usize,
+    others: &[&Document],
+    keywords: &[&str],
+) -> Vec<String> {
+    // Each word's distance in lines, or past the end for other sources, and
+    // how often it was seen.
+    let mut found: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut add = |word: &str, distance: usize| {
+        if word.len() > prefix.len() && word.starts_with(prefix) {
+            let entry = found.entry(word.to_string()).or_insert((distance, 0));
+            entry.0 = entry.0.min(distance);
+            entry.1 += 1;
+        }
+    };
+    for (index, line) in lines(document).enumerate() {
+        for word in line.unicode_words() {
+            add(word, index.abs_diff(y));
+        }
+    }
+    for other in others {
+        for line in lines(other) {
+            for word in line.unicode_words() {
+                add(word, usize::MAX - 1);
+            }
+        }
+    }
+    for keyword in keywords {
+        add(keyword, usize::MAX);
+    }
+    let mut ranked: Vec<(String, (usize, usize))> = found.into_iter().collect();
+    ranked.sort_by(|(a, (a_distance, a_count)), (b, (b_distance, b_count))| {
+        a_distance
+            .cmp(b_distance)
+            .then(b_count.cmp(a_count))
+            .then(a.cmp(b))
+    });
+    ranked
+        .into_iter()
+        .take(MAX_COMPLETIONS)
+        .map(|(word, _)| word)
+        .collect()
+}
+
+fn lines(document: &Document) -> impl Iterator<Item = String> + '_ {
+    (0..document.len())
+        .map_while(move |y| document.row(y))
+        .map(|row| String::from_utf8_lossy(row.as_bytes()).to_string())
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let document = Document::from_string(
+            "let snuggle_count = 1;\nsnuggles snuggles\n\nsnu".to_string(),
+        )
+        .unwrap();
+        let other = Document::from_string("snuggly snuggle_count".to_string()).unwrap();
+        assert_eq!(
+            candidates("snu", &document, 3, &[&other], &["snuck", "snuggles"]),
+            vec!["snuggles", "snuggle_count", "snuggly", "snuck"]
+        );
+        assert!(candidates("nyaa", &document, 0, &[], &[]).is_empty());
+    }
+}
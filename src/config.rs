@@ -0,0 +1,117 @@
+#[cfg(feature = "tui")]
+use crate::statusbar;
+#[cfg(feature = "tui")]
+use crate::uwu::Intensity;
+#[cfg(feature = "tui")]
+use crate::{ColorDepth, FileType, UwuScope};
+#[cfg(feature = "tui")]
+use std::collections::HashMap;
+use std::env;
+#[cfg(feature = "tui")]
+use std::fs;
+#[cfg(feature = "tui")]
+use std::path::Path;
+use std::path::PathBuf;
+
+#[cfg(feature = "tui")]
+const CONFIG_FILE: &str = "config";
+
+/// The editor's settings, read from `config` in [`dir`].
+#[cfg(feature = "tui")]
+pub struct Config {
+    pub theme: String,
+    pub color_depth: Option<ColorDepth>,
+    pub status_left: String,
+    pub status_right: String,
+    pub spell_check: bool,
+    pub tab_width: usize,
+    pub uwu_intensity: Intensity,
+    /// Uwu scopes set with `uwu_scope.<file type>`, by file type name.
+    pub uwu_scopes: HashMap<String, UwuScope>,
+}
+
+#[cfg(feature = "tui")]
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: String::from("pastel"),
+            color_depth: None,
+            status_left: String::from(statusbar::DEFAULT_LEFT),
+            status_right: String::from(statusbar::DEFAULT_RIGHT),
+            spell_check: true,
+            tab_width: 4,
+            uwu_intensity: Intensity::Light,
+            uwu_scopes: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(feature = "tui")]
+impl Config {
+    pub fn load() -> Self {
+        if let Some(path) = dir().map(|dir| dir.join(CONFIG_FILE)) {
+            if let Ok(config) = Self::open(&path) {
+                return config;
+            }
+        }
+        Self::default()
+    }
+    pub fn open(path: &Path) -> Result<Self, std::io::Error> {
+        let contents = fs::read_to_string(path)?;
+        Ok(Self::parse(&contents))
+    }
+    pub fn parse(contents: &str) -> Self {
+        let mut config = Self::default();
+        for (key, value) in entries(contents) {
+            match key {
+                "theme" => config.theme = value.to_string(),
+                "colors" => config.color_depth = ColorDepth::from(value),
+                "status_left" => config.status_left = value.to_string(),
+                "status_right" => config.status_right = value.to_string(),
+                "spell_check" => config.spell_check = value != "off" && value != "false",
+                "uwu_intensity" => {
+                    if let Some(intensity) = Intensity::from_name(value) {
+                        config.uwu_intensity = intensity;
+                    }
+                }
+                "tab_width" => {
+                    if let Some(width) = value.parse().ok().filter(|width| *width > 0) {
+                        config.tab_width = width;
+                    }
+                }
+                _ => {
+                    let file_type = key.strip_prefix("uwu_scope.").and_then(FileType::from_name);
+                    if let (Some(file_type), Some(scope)) = (file_type, UwuScope::from_name(value)) {
+                        config.uwu_scopes.insert(file_type.name(), scope);
+                    }
+                }
+            }
+        }
+        config
+    }
+}
+
+/// Yields the `key = value` pairs of a kyun config-style file, skipping
+/// blank lines and lines starting with `#`.
+pub fn entries(contents: &str) -> impl Iterator<Item = (&str, &str)> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+/// The directory user files (config, themes, ...) are read from.
+pub fn dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("KYUN_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("kyun"));
+    }
+    if let Some(dir) = env::var_os("APPDATA") {
+        return Some(PathBuf::from(dir).join("kyun"));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("kyun"))
+}
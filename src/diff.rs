@@ -0,0 +1,98 @@
+/// One line of a line-by-line diff.
+#[derive(PartialEq, Debug)]
+pub enum Change<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+    /// This many old lines were replaced with this many new ones, for
+    /// differences too large to line up.
+    Replaced(usize, usize),
+}
+
+/// The most cells the longest common subsequence table may have. Larger
+/// differences are shown as a plain replacement.
+const MAX_CELLS: usize = 1 << 20;
+
+/// Diffs two texts line by line with a longest common subsequence. The
+/// common start and end are skipped first, which keeps the table small for
+/// the usual handful of changes.
+pub fn lines<'a>(old: &'a str, new: &'a str) -> Vec<Change<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old
+        .iter()
+        .zip(&new)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    let mut changes: Vec<Change> = old[..prefix].iter().map(|line| Change::Same(line)).collect();
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_CELLS {
+        changes.push(Change::Replaced(a.len(), b.len()));
+        changes.extend(old[old.len() - suffix..].iter().map(|line| Change::Same(line)));
+        return changes;
+    }
+
+    // lengths[i][j] is the LCS length of a[i..] and b[j..].
+    let mut lengths = vec![vec![0_usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            changes.push(Change::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push(Change::Removed(a[i]));
+            i += 1;
+        } else {
+            changes.push(Change::Added(b[j]));
+            j += 1;
+        }
+    }
+    changes.extend(old[old.len() - suffix..].iter().map(|line| Change::Same(line)));
+    changes
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let changes = lines("hewwo\nworld\nbye\n", "hewwo\nwowwd\nbye\nagain\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::Same("hewwo"),
+                Change::Removed("world"),
+                Change::Added("wowwd"),
+                Change::Same("bye"),
+                Change::Added("again"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lines_large() {
+        let old: String = (0..5000).fold("same\n".to_string(), |text, i| text + &format!("owd {}\n", i));
+        let new: String = (0..4000).fold("same\n".to_string(), |text, i| text + &format!("new {}\n", i));
+        let changes = lines(&old, &new);
+        assert_eq!(changes, vec![Change::Same("same"), Change::Replaced(5000, 4000)]);
+    }
+}
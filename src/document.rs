@@ -1,32 +1,229 @@
+use crate::Dictionary;
+use crate::large::LargeFile;
+use crate::highlighting;
 use crate::FileType;
-use crate::Position;
 use crate::Row;
-use crate::SearchDirection;
-use std::fs;
-use std::io::{Error, Write};
+use crate::Snippets;
+use crate::Substitutions;
+use crate::Statistics;
+use crate::UwuScope;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::Error;
+use std::path::Path;
+use std::time::SystemTime;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Which way `Document::find` searches.
+#[derive(PartialEq, Copy, Clone)]
+pub enum SearchDirection {
+    Forward,
+    Backward,
+}
+
+/// A place in a document: `x` counts graphemes, `y` rows.
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct Position {
+    pub x: usize,
+    pub y: usize,
+}
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    fn detect(contents: &str) -> Self {
+        match contents.find('\n') {
+            Some(index) if contents[..index].ends_with('\r') => LineEnding::CrLf,
+            _ => LineEnding::Lf,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+        }
+    }
+    fn as_bytes(self) -> &'static [u8] {
+        match self {
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+        }
+    }
+}
+
+/// The bracket that closes `c`, if `c` opens one.
+fn closing_bracket(c: char) -> Option<char> {
+    match c {
+        '{' => Some('}'),
+        '(' => Some(')'),
+        '[' => Some(']'),
+        _ => None,
+    }
+}
+
+/// What typing next to a pair does besides inserting the character.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum PairEdit {
+    /// Insert both halves and put the cursor between them.
+    Insert(char, char),
+    /// Move over the closing half that is already there.
+    StepOver,
+    /// Delete both halves of an empty pair.
+    DeletePair,
+}
+
+/// What typing `key`, or Backspace for `None`, does to the `pairs` around
+/// the cursor, given the characters right before and after it and the
+/// highlighting of the text it is in. Comments are left alone, and pairs
+/// don't open inside strings.
+pub fn pair_edit(
+    pairs: &[(char, char)],
+    key: Option<char>,
+    previous: Option<char>,
+    next: Option<char>,
+    inside: highlighting::Type,
+) -> Option<PairEdit> {
+    if matches!(inside, highlighting::Type::Comment | highlighting::Type::MultilineComment) {
+        return None;
+    }
+    let c = match key {
+        Some(c) => c,
+        None => {
+            let empty = pairs
+                .iter()
+                .any(|(open, close)| previous == Some(*open) && next == Some(*close));
+            return if empty { Some(PairEdit::DeletePair) } else { None };
+        }
+    };
+    let (open, close) = pairs.iter().copied().find(|(open, close)| *open == c || *close == c)?;
+    if c == close && next == Some(close) {
+        return Some(PairEdit::StepOver);
+    }
+    // Only pair in front of nothing, space or a closing half, and not
+    // quotes right after a word, where they are more likely apostrophes.
+    let closes = |c: char| pairs.iter().any(|(_, close)| *close == c);
+    let fits_before = next.is_none_or(|next| next.is_whitespace() || closes(next));
+    let after_word = previous.is_some_and(char::is_alphanumeric);
+    if c != open || !fits_before || (open == close && after_word) || inside == highlighting::Type::String {
+        return None;
+    }
+    Some(PairEdit::Insert(open, close))
+}
+
+/// The file as it was on disk when it was last read or written.
+#[derive(Clone)]
+struct DiskState {
+    file_name: String,
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+impl DiskState {
+    fn read(file_name: &str, contents: &[u8]) -> Result<Self, Error> {
+        let metadata = fs::metadata(file_name)?;
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+        Ok(Self {
+            file_name: file_name.to_string(),
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: hasher.finish(),
+        })
+    }
+}
+
+const MAX_UNDO: usize = 1000;
+/// Files bigger than this are opened read-only, a screenful at a time.
+pub const LARGE_FILE: u64 = 32 * 1024 * 1024;
+/// Files bigger than this are not highlighted or spell checked.
+const HIGHLIGHT_LIMIT: u64 = 4 * 1024 * 1024;
+/// Spaces per indentation level when the document shows no style of its own.
+const DEFAULT_INDENT: usize = 4;
+/// How many rows are looked at to find the indentation style.
+const INDENT_SAMPLE: usize = 1000;
+/// How many rows around the screen are kept when reading a large file.
+const WINDOW_MARGIN: usize = 500;
+
+enum Edit {
+    Insert {
+        at: Position,
+        text: String,
+        new_row: bool,
+    },
+    Delete {
+        at: Position,
+        text: String,
+    },
+}
 
 #[derive(Default)]
 pub struct Document {
     rows: Vec<Row>,
     pub file_name: Option<String>,
     dirty: bool,
+    /// Bumped on every change, so the swap file is only rewritten when needed.
+    revision: usize,
     file_type: FileType,
+    line_ending: LineEnding,
+    stats: Statistics,
+    session_start_words: usize,
+    undo_stack: Vec<Vec<Edit>>,
+    pending_edits: Vec<Edit>,
+    disk: Option<DiskState>,
+    large: Option<LargeFile>,
+    /// For large files, the line `rows[0]` holds.
+    window_start: usize,
+    no_highlighting: bool,
+    read_only: bool,
 }
 
 impl Document {
+    /// Opens `filename`, read-only if we may not write to it.
     pub fn open(filename: &str) -> Result<Self, std::io::Error> {
+        let metadata = fs::metadata(filename)?;
+        // The permission bits don't know about owners or read-only mounts,
+        // so try it. Opening for writing doesn't truncate.
+        let read_only = OpenOptions::new().write(true).open(filename).is_err();
+        if metadata.len() > LARGE_FILE {
+            return Ok(Self {
+                read_only,
+                file_name: Some(filename.to_string()),
+                file_type: FileType::from(filename),
+                large: Some(LargeFile::open(Path::new(filename))?),
+                no_highlighting: true,
+                ..Self::default()
+            });
+        }
+        let mut document = Self::open_fully(filename)?;
+        document.read_only = read_only;
+        Ok(document)
+    }
+    /// Reads all of `filename`, however big it is.
+    pub fn open_fully(filename: &str) -> Result<Self, std::io::Error> {
         let contents = fs::read_to_string(filename)?;
         let file_type = FileType::from(filename);
         let mut rows = Vec::new();
         for value in contents.lines() {
             rows.push(Row::from(value));
         }
-        Ok(Self {
+        Ok(Self::with_stats(Self {
             rows,
             file_name: Some(filename.to_string()),
             dirty: false,
             file_type,
-        })
+            line_ending: LineEnding::detect(&contents),
+            disk: DiskState::read(filename, contents.as_bytes()).ok(),
+            no_highlighting: contents.len() as u64 > HIGHLIGHT_LIMIT,
+            ..Self::default()
+        }))
     }
 
     pub fn from_string(string: String) -> Result<Self, std::io::Error> {
@@ -35,24 +232,263 @@ impl Document {
             rows.push(Row::from(value));
         }
 
-        Ok(Self {
+        Ok(Self::with_stats(Self {
+            line_ending: LineEnding::detect(&string),
             rows,
             file_name: None,
             dirty: false,
             file_type: FileType::default(),
-        })
+            ..Self::default()
+        }))
+    }
+    fn with_stats(mut self) -> Self {
+        self.stats = self.rows_stats(0, self.rows.len());
+        self.session_start_words = self.stats.words;
+        self
+    }
+    /// Sums the statistics of `rows[start..end]`, counting a paragraph
+    /// wherever a non-blank row follows a blank one.
+    fn rows_stats(&self, start: usize, end: usize) -> Statistics {
+        let mut stats = Statistics::default();
+        for y in start..end.min(self.rows.len()) {
+            let row = &self.rows[y];
+            stats += row.stats();
+            if !row.is_blank() && (y == 0 || self.rows[y - 1].is_blank()) {
+                stats.paragraphs += 1;
+            }
+        }
+        stats
+    }
+    pub fn stats(&self) -> Statistics {
+        self.stats
+    }
+    /// How many words typing changed, for the uwu density. Rows only
+    /// recount after they changed.
+    pub fn transformed_words(&mut self, dictionary: &Dictionary) -> usize {
+        self.rows.iter_mut().map(|row| row.transformed_words(dictionary)).sum()
+    }
+    /// Words written (or, if negative, removed) since the document was opened.
+    pub fn session_words(&self) -> isize {
+        self.stats.words as isize - self.session_start_words as isize
     }
     pub fn file_type(&self) -> String {
         self.file_type.name()
     }
+    pub fn set_file_type(&mut self, file_type: FileType) {
+        self.file_type = file_type;
+        self.unhighlight_rows(0);
+    }
+    /// One level of indentation the way the document does it: a tab if
+    /// most indented rows start with one, otherwise the most common step
+    /// in spaces between one row and the next.
+    pub fn indent_unit(&self) -> String {
+        let (mut tabs, mut spaces) = (0, 0);
+        let mut steps: HashMap<usize, usize> = HashMap::new();
+        let mut previous = 0;
+        for row in self.rows.iter().take(INDENT_SAMPLE).filter(|row| !row.is_blank()) {
+            let indentation = row.indentation();
+            if indentation.starts_with('\t') {
+                tabs += 1;
+                continue;
+            }
+            let width = indentation.chars().filter(|c| *c == ' ').count();
+            if width > 0 {
+                spaces += 1;
+            }
+            if width != previous {
+                *steps.entry(width.abs_diff(previous)).or_default() += 1;
+            }
+            previous = width;
+        }
+        if tabs > spaces {
+            return "\t".to_string();
+        }
+        let step = steps
+            .into_iter()
+            .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+            .map_or(DEFAULT_INDENT, |(step, _)| step);
+        " ".repeat(step)
+    }
+    /// Replaces the snippet trigger before `at` with its snippet, indented
+    /// like the line, and returns the tab stops in the order they are
+    /// jumped to, each with the length of its text.
+    pub fn expand_snippet(&mut self, at: &Position, snippets: &Snippets) -> Option<Vec<(Position, usize)>> {
+        let line = String::from_utf8_lossy(self.rows.get(at.y)?.as_bytes()).to_string();
+        let before: Vec<&str> = line.graphemes(true).take(at.x).collect();
+        let trigger_len = before
+            .iter()
+            .rev()
+            .take_while(|grapheme| !grapheme.trim().is_empty())
+            .count();
+        let trigger = before[before.len() - trigger_len..].concat();
+        let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+        let body = snippets.find(&self.file_type(), &trigger)?.body(&indent);
+        let start = Position {
+            x: before.len() - trigger_len,
+            y: at.y,
+        };
+        for _ in 0..trigger_len {
+            self.delete(&start);
+        }
+        self.insert_text(&start, &body.lines.join("\n"));
+        let stops = body
+            .stops
+            .into_iter()
+            .map(|(stop, len)| {
+                let x = if stop.y == 0 { start.x + stop.x } else { stop.x };
+                (Position { x, y: start.y + stop.y }, len)
+            })
+            .collect();
+        Some(stops)
+    }
+    /// The text Enter inserts at `at`: a line break and the indentation of
+    /// the line, one level deeper after an opening bracket. Between a pair
+    /// of brackets a second line break puts the closing one on its own line.
+    pub fn line_break(&self, at: &Position) -> String {
+        let line = match self.rows.get(at.y) {
+            Some(row) => String::from_utf8_lossy(row.as_bytes()).to_string(),
+            None => return "\n".to_string(),
+        };
+        let graphemes: Vec<&str> = line.graphemes(true).collect();
+        let x = at.x.min(graphemes.len());
+        let (before, after) = (graphemes[..x].concat(), graphemes[x..].concat());
+        let indent: String = before.chars().take_while(|c| c.is_whitespace()).collect();
+        let opener = before.trim_end().chars().last().and_then(closing_bracket);
+        let mut text = format!("\n{}", indent);
+        if let Some(closer) = opener {
+            text += &self.indent_unit();
+            if after.trim_start().starts_with(closer) {
+                text = format!("{}\n{}", text, indent);
+            }
+        }
+        text
+    }
+    /// The line up to `at` once typing `c` there takes one level of
+    /// indentation off it, if `c` is a closing bracket and nothing but
+    /// indentation comes before it.
+    pub fn dedented(&self, at: &Position, c: char) -> Option<String> {
+        if !matches!(c, '}' | ')' | ']') {
+            return None;
+        }
+        let before: String = String::from_utf8_lossy(self.rows.get(at.y)?.as_bytes())
+            .graphemes(true)
+            .take(at.x)
+            .collect();
+        if before.is_empty() || !before.trim().is_empty() {
+            return None;
+        }
+        let unit = self.indent_unit();
+        let dedented = match before.strip_suffix(unit.as_str()) {
+            Some(dedented) => dedented.to_string(),
+            None => {
+                let mut graphemes: Vec<&str> = before.graphemes(true).collect();
+                graphemes.pop();
+                graphemes.concat()
+            }
+        };
+        Some(dedented)
+    }
+    /// Inserts `text`, which may span several lines, at `at` and returns
+    /// the position right after it.
+    pub fn insert_text(&mut self, at: &Position, text: &str) -> Position {
+        let mut at = at.clone();
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.insert(&at, '\n');
+                at = Position { x: 0, y: at.y + 1 };
+            }
+            self.insert_str(&at, line);
+            at.x += line.graphemes(true).count();
+        }
+        at
+    }
+    /// [`pair_edit`] for typing `key` at `at`, or Backspace for `None`.
+    pub fn pair_edit(&mut self, at: &Position, key: Option<char>) -> Option<PairEdit> {
+        let char_at = |x| self.rows.get(at.y)?.grapheme(x)?.chars().next();
+        let previous = at.x.checked_sub(1).and_then(char_at);
+        let next = char_at(at.x);
+        let inside = self.highlighting_at(at, ' ');
+        pair_edit(self.pairs(), key, previous, next, inside)
+    }
+    pub fn pairs(&self) -> &[(char, char)] {
+        self.file_type.pairs()
+    }
+    pub fn uwu_scope(&self) -> UwuScope {
+        self.file_type.uwu_scope()
+    }
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+    pub fn word_count(&self) -> usize {
+        self.stats.words
+    }
+    /// Counts the graphemes between two positions, line breaks included.
+    pub fn count_between(&self, a: &Position, b: &Position) -> usize {
+        let (start, end) = if (a.y, a.x) <= (b.y, b.x) { (a, b) } else { (b, a) };
+        if start.y == end.y {
+            return end.x.saturating_sub(start.x);
+        }
+        let mut count = self
+            .row(start.y)
+            .map_or(0, |row| row.len().saturating_sub(start.x) + 1);
+        for y in start.y.saturating_add(1)..end.y {
+            count += self.row(y).map_or(0, |row| row.len() + 1);
+        }
+        count + end.x
+    }
     pub fn row(&self, index: usize) -> Option<&Row> {
-        self.rows.get(index)
+        self.rows.get(index.checked_sub(self.window_start)?)
     }
     pub fn is_empty(&self) -> bool {
-        self.rows.is_empty()
+        self.large.is_none() && self.rows.is_empty()
     }
     pub fn len(&self) -> usize {
-        self.rows.len()
+        self.large.as_ref().map_or(self.rows.len(), LargeFile::lines)
+    }
+    /// Whether this is a large file shown a screenful at a time. Those are
+    /// read-only until loaded fully.
+    pub fn is_large(&self) -> bool {
+        self.large.is_some()
+    }
+    /// Whether a large file's lines are still being counted.
+    pub fn is_indexing(&self) -> bool {
+        self.large.as_ref().is_some_and(|large| !large.is_indexed())
+    }
+    /// Makes sure the rows `start..start + height` of a large file are read.
+    pub fn load_window(&mut self, start: usize, height: usize) -> Result<(), Error> {
+        let large = if let Some(large) = &self.large {
+            large
+        } else {
+            return Ok(());
+        };
+        let end = start.saturating_add(height).min(large.lines());
+        if start >= self.window_start && end <= self.window_start + self.rows.len() {
+            return Ok(());
+        }
+        let window_start = start.saturating_sub(WINDOW_MARGIN);
+        let lines = large.read_lines(window_start, height + 2 * WINDOW_MARGIN)?;
+        self.rows = lines.iter().map(|line| Row::from(line.as_str())).collect();
+        self.window_start = window_start;
+        Ok(())
+    }
+    /// Whether edits are refused, because the document was opened with
+    /// `--readonly`, its file can't be written or it is a large file.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || self.is_large()
+    }
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+    /// Reads all of a large file so it can be edited.
+    pub fn load_fully(&mut self) -> Result<(), Error> {
+        if let (true, Some(file_name)) = (self.is_large(), &self.file_name) {
+            *self = Self {
+                file_type: std::mem::take(&mut self.file_type),
+                read_only: self.read_only,
+                ..Self::open_fully(file_name)?
+            };
+        }
+        Ok(())
     }
     fn insert_newline(&mut self, at: &Position) {
         if at.y > self.rows.len() {
@@ -67,10 +503,18 @@ impl Document {
         self.rows.insert(at.y + 1, new_row);
     }
     pub fn insert(&mut self, at: &Position, c: char) {
-        if at.y > self.rows.len() {
+        if at.y > self.rows.len() || self.is_read_only() {
             return;
         }
         self.dirty = true;
+        self.revision += 1;
+        self.pending_edits.push(Edit::Insert {
+            at: at.clone(),
+            text: c.to_string(),
+            new_row: at.y == self.rows.len(),
+        });
+        // The row after an edit may start or stop being a paragraph start.
+        let before = self.rows_stats(at.y, at.y.saturating_add(2));
         if c == '\n' {
             self.insert_newline(at);
         } else if at.y == self.rows.len() {
@@ -81,6 +525,9 @@ impl Document {
             let row = &mut self.rows[at.y];
             row.insert(at.x, c);
         }
+        let added_rows = if c == '\n' { 3 } else { 2 };
+        self.stats -= before;
+        self.stats += self.rows_stats(at.y, at.y.saturating_add(added_rows));
         self.unhighlight_rows(at.y);
     }
 
@@ -92,11 +539,25 @@ impl Document {
     }
     pub fn delete(&mut self, at: &Position) {
         let len = self.rows.len();
-        if at.y >= len {
+        if at.y >= len || self.is_read_only() {
             return;
         }
+        let joins_rows = at.x == self.rows[at.y].len() && at.y + 1 < len;
+        let deleted = if joins_rows {
+            "\n"
+        } else if let Some(grapheme) = self.rows[at.y].grapheme(at.x) {
+            grapheme
+        } else {
+            return;
+        };
         self.dirty = true;
-        if at.x == self.rows[at.y].len() && at.y + 1 < len {
+        self.revision += 1;
+        self.pending_edits.push(Edit::Delete {
+            at: at.clone(),
+            text: deleted.to_string(),
+        });
+        let before = self.rows_stats(at.y, at.y + if joins_rows { 3 } else { 2 });
+        if joins_rows {
             let next_row = self.rows.remove(at.y + 1);
             let row = &mut self.rows[at.y];
             row.append(&next_row);
@@ -104,23 +565,207 @@ impl Document {
             let row = &mut self.rows[at.y];
             row.delete(at.x);
         }
+        self.stats -= before;
+        self.stats += self.rows_stats(at.y, at.y + 2);
         self.unhighlight_rows(at.y);
     }
+    /// Swaps the word ending at `at` for its entry in `substitutions` and
+    /// returns where `cursor` ends up on the same text. This is an undo
+    /// step of its own, so one undo brings the word back as typed.
+    pub fn substitute_word(&mut self, at: &Position, cursor: &Position, substitutions: &Substitutions) -> Position {
+        let found = at.x.checked_sub(1).and_then(|x| {
+            let (start, word) = self.rows.get(at.y)?.word_at(x)?;
+            let len = word.graphemes(true).count();
+            if start + len != at.x {
+                return None;
+            }
+            Some((start, len, substitutions.get(word)?))
+        });
+        let (start, len, replacement) = match found {
+            Some(found) => found,
+            None => return cursor.clone(),
+        };
+        self.commit();
+        self.replace(&Position { x: start, y: at.y }, len, &replacement);
+        let new_len = replacement.graphemes(true).count();
+        Position {
+            x: (cursor.x + new_len).saturating_sub(len),
+            y: cursor.y,
+        }
+    }
+    /// Replaces the `len` graphemes at `at` with `text`, which must not
+    /// contain line breaks.
+    pub fn replace(&mut self, at: &Position, len: usize, text: &str) {
+        for _ in 0..len {
+            self.delete(at);
+        }
+        self.insert_str(at, text);
+    }
+    /// Inserts `text`, which must not contain line breaks, in one go.
+    pub fn insert_str(&mut self, at: &Position, text: &str) {
+        if at.y > self.rows.len() || text.is_empty() || self.is_read_only() {
+            return;
+        }
+        self.dirty = true;
+        self.revision += 1;
+        self.pending_edits.push(Edit::Insert {
+            at: at.clone(),
+            text: text.to_string(),
+            new_row: at.y == self.rows.len(),
+        });
+        let before = self.rows_stats(at.y, at.y.saturating_add(2));
+        if at.y == self.rows.len() {
+            self.rows.push(Row::from(text));
+        } else {
+            self.rows[at.y].insert_str(at.x, text);
+        }
+        self.stats -= before;
+        self.stats += self.rows_stats(at.y, at.y.saturating_add(2));
+        self.unhighlight_rows(at.y);
+    }
+    /// Closes the current undo step. Everything edited since the previous
+    /// call is undone together.
+    pub fn commit(&mut self) {
+        if self.pending_edits.is_empty() {
+            return;
+        }
+        let edits = std::mem::take(&mut self.pending_edits);
+        self.undo_stack.push(edits);
+        if self.undo_stack.len() > MAX_UNDO {
+            self.undo_stack.remove(0);
+        }
+    }
+    /// Reverts the last undo step and returns where it started.
+    pub fn undo(&mut self) -> Option<Position> {
+        self.commit();
+        let edits = self.undo_stack.pop()?;
+        let mut position = None;
+        for edit in edits.into_iter().rev() {
+            match edit {
+                Edit::Insert { at, new_row, .. } if new_row => self.remove_row(at.y),
+                Edit::Insert { at, text, .. } => {
+                    let len = if text == "\n" { 1 } else { text.graphemes(true).count() };
+                    for _ in 0..len {
+                        self.delete(&at);
+                    }
+                    position = Some(at);
+                }
+                Edit::Delete { at, text } => {
+                    if text == "\n" {
+                        self.insert(&at, '\n');
+                    } else {
+                        self.insert_str(&at, &text);
+                    }
+                    position = Some(at);
+                }
+            }
+        }
+        // Undoing is not an edit that can be undone in turn.
+        self.pending_edits.clear();
+        position
+    }
+    fn remove_row(&mut self, y: usize) {
+        if y >= self.rows.len() {
+            return;
+        }
+        self.dirty = true;
+        self.revision += 1;
+        let before = self.rows_stats(y, y.saturating_add(2));
+        self.rows.remove(y);
+        self.stats -= before;
+        self.stats += self.rows_stats(y, y.saturating_add(1));
+        self.unhighlight_rows(y);
+    }
     pub fn save(&mut self) -> Result<(), Error> {
+        // Only part of a large file is in memory, so there is nothing to write.
+        if self.is_large() {
+            return Ok(());
+        }
         if let Some(file_name) = &self.file_name {
-            let mut file = fs::File::create(file_name)?;
-            self.file_type = FileType::from(file_name);
-            for row in &mut self.rows {
-                file.write_all(row.as_bytes())?;
-                file.write_all(b"\n")?;
+            // Keep a type picked with `:filetype` unless the name says otherwise.
+            let file_type = FileType::from(file_name);
+            if file_type.name() != FileType::default().name() {
+                self.file_type = file_type;
             }
+            let contents = self.contents();
+            fs::write(file_name, &contents)?;
+            self.disk = DiskState::read(file_name, &contents).ok();
             self.dirty = false;
         }
         Ok(())
     }
+    /// Whether someone else changed the file since it was opened or saved.
+    /// A file that was only touched, or is gone, does not count.
+    pub fn changed_on_disk(&mut self) -> bool {
+        let (file_name, disk) = match (&self.file_name, &self.disk) {
+            (Some(file_name), Some(disk)) if *file_name == disk.file_name => (file_name, disk),
+            _ => return false,
+        };
+        let metadata = if let Ok(metadata) = fs::metadata(file_name) {
+            metadata
+        } else {
+            return false;
+        };
+        if metadata.modified().ok() == disk.modified && metadata.len() == disk.len {
+            return false;
+        }
+        let current = if let Ok(current) = fs::read(file_name)
+            .and_then(|contents| DiskState::read(file_name, &contents))
+        {
+            current
+        } else {
+            return false;
+        };
+        let changed = current.hash != disk.hash;
+        if !changed {
+            self.disk = Some(current);
+        }
+        changed
+    }
+    /// Reads the file again, dropping unsaved changes and undo history but
+    /// keeping the file type and the session's word count.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let file_name = if let Some(file_name) = &self.file_name {
+            file_name.clone()
+        } else {
+            return Ok(());
+        };
+        let reloaded = Self::open(&file_name)?;
+        *self = Self {
+            file_type: std::mem::take(&mut self.file_type),
+            session_start_words: self.session_start_words,
+            revision: self.revision + 1,
+            read_only: self.read_only || reloaded.read_only,
+            ..reloaded
+        };
+        Ok(())
+    }
     pub fn is_dirty(&self) -> bool {
         self.dirty
     }
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+    /// The bytes `save` writes.
+    pub fn contents(&self) -> Vec<u8> {
+        let mut contents = Vec::new();
+        for row in &self.rows {
+            contents.extend_from_slice(row.as_bytes());
+            contents.extend_from_slice(self.line_ending.as_bytes());
+        }
+        contents
+    }
+    /// Replaces the text with `contents`, e.g. from a swap file, keeping the
+    /// file name. The document counts as changed until it is saved.
+    pub fn recover(&mut self, contents: &str) {
+        self.rows = contents.lines().map(Row::from).collect();
+        self.line_ending = LineEnding::detect(contents);
+        self.undo_stack.clear();
+        self.pending_edits.clear();
+        self.dirty = true;
+        self.revision += 1;
+        self.stats = self.rows_stats(0, self.rows.len());
+    }
     pub fn find(&self, query: &str, at: &Position, direction: SearchDirection) -> Option<Position> {
         if at.y >= self.rows.len() {
             return None;
@@ -139,7 +784,7 @@ impl Document {
         };
         for _ in start..end {
             if let Some(row) = self.rows.get(position.y) {
-                if let Some(x) = row.find(&query, position.x, direction) {
+                if let Some(x) = row.find(query, position.x, direction) {
                     position.x = x;
                     return Some(position);
                 }
@@ -156,7 +801,15 @@ impl Document {
         }
         None
     }
-    pub fn highlight(&mut self, word: &Option<String>, until: Option<usize>) {
+    pub fn highlight(
+        &mut self,
+        word: &Option<String>,
+        until: Option<usize>,
+        dictionary: Option<&Dictionary>,
+    ) {
+        if self.no_highlighting {
+            return;
+        }
         let mut start_with_comment = false;
         let until = if let Some(until) = until {
             if until.saturating_add(1) < self.rows.len() {
@@ -167,12 +820,220 @@ impl Document {
         } else {
             self.rows.len()
         };
+        let is_code = self.file_type.is_code();
         for row in &mut self.rows[..until] {
             start_with_comment = row.highlight(
-                &self.file_type.highlighting_options(),
+                self.file_type.highlighting_options(),
                 word,
                 start_with_comment,
             );
+            if let Some(dictionary) = dictionary {
+                row.spell_check(dictionary, is_code);
+            }
         }
     }
+    /// Highlights the grapheme ranges `marked` in row `y` for review.
+    pub fn mark(&mut self, y: usize, marked: Vec<(usize, usize)>) {
+        if let Some(row) = self.rows.get_mut(y) {
+            row.mark(marked);
+        }
+    }
+    /// The highlighting `c` would get if it were typed at `at`, so strings
+    /// and comments can be told from code before anything is inserted.
+    pub fn highlighting_at(&mut self, at: &Position, c: char) -> highlighting::Type {
+        if self.no_highlighting {
+            return highlighting::Type::None;
+        }
+        let start_with_comment = match at.y.checked_sub(1) {
+            Some(y) => {
+                self.highlight(&None, Some(y), None);
+                self.rows.get(y).is_some_and(Row::ends_in_comment)
+            }
+            None => false,
+        };
+        let opts = self.file_type.highlighting_options();
+        let mut row = self.rows.get(at.y).map_or_else(Row::default, |row| {
+            Row::from(String::from_utf8_lossy(row.as_bytes()).as_ref())
+        });
+        row.insert(at.x, c);
+        row.highlight(opts, &None, start_with_comment);
+        row.highlighting().get(at.x).copied().unwrap_or(highlighting::Type::None)
+    }
+    pub fn reset_spelling(&mut self) {
+        for row in &mut self.rows {
+            row.reset_spelling();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_indent_unit() {
+        let spaces = "fn main() {\n  if x {\n    y();\n  }\n  /*\n   * z\n   */\n}";
+        assert_eq!(Document::from_string(spaces.to_string()).unwrap().indent_unit(), "  ");
+        let tabs = "fn main() {\n\tx();\n\t\ty();\n}";
+        assert_eq!(Document::from_string(tabs.to_string()).unwrap().indent_unit(), "\t");
+        assert_eq!(Document::default().indent_unit(), "    ");
+    }
+
+    #[test]
+    fn test_highlighting_at() {
+        let mut document =
+            Document::from_string("let x = \"hi\";\n/* hewwo\nstiww */ x".to_string()).unwrap();
+        document.set_file_type(FileType::from("main.rs"));
+        let at = |x, y| Position { x, y };
+        assert_eq!(document.highlighting_at(&at(5, 0), 'l'), highlighting::Type::None);
+        assert_eq!(document.highlighting_at(&at(10, 0), 'l'), highlighting::Type::String);
+        assert_eq!(document.highlighting_at(&at(12, 0), 'l'), highlighting::Type::None);
+        assert_eq!(document.highlighting_at(&at(2, 2), 'l'), highlighting::Type::MultilineComment);
+        assert_eq!(document.highlighting_at(&at(9, 2), 'l'), highlighting::Type::None);
+        document.insert_str(&at(8, 1), " */");
+        assert_eq!(document.highlighting_at(&at(2, 2), 'l'), highlighting::Type::None);
+    }
+
+    #[test]
+    fn test_expand_snippet() {
+        let snippets = Snippets::bundled();
+        for typed in &["  nuzzwes", "  n-nuzzwes", "  nyuzzwes"] {
+            let mut document = Document::from_string(typed.to_string()).unwrap();
+            document.set_file_type(FileType::from("main.uwu"));
+            let at = Position { x: typed.len(), y: 0 };
+            let stops = document.expand_snippet(&at, &snippets).unwrap();
+            assert_eq!(
+                String::from_utf8(document.contents()).unwrap(),
+                "  nuzzles *x* wess twan 10\n      \n  stawp\n"
+            );
+            assert_eq!(stops[0], (Position { x: 11, y: 0 }, 1));
+        }
+        let mut document = Document::from_string("nuzzwes".to_string()).unwrap();
+        assert!(document.expand_snippet(&Position { x: 7, y: 0 }, &snippets).is_none());
+    }
+
+    #[test]
+    fn test_pair_edit() {
+        let pairs = [('(', ')'), ('"', '"')];
+        let none = highlighting::Type::None;
+        let edit = |key, previous, next, inside| pair_edit(&pairs, key, previous, next, inside);
+        assert_eq!(edit(Some('('), None, None, none), Some(PairEdit::Insert('(', ')')));
+        assert_eq!(edit(Some('('), None, Some('x'), none), None);
+        assert_eq!(edit(Some('"'), Some('x'), None, none), None);
+        assert_eq!(edit(Some(')'), Some('('), Some(')'), none), Some(PairEdit::StepOver));
+        assert_eq!(edit(None, Some('('), Some(')'), none), Some(PairEdit::DeletePair));
+        assert_eq!(edit(None, Some('('), Some('x'), none), None);
+        let comment = highlighting::Type::Comment;
+        assert_eq!(edit(Some('('), None, None, comment), None);
+        assert_eq!(edit(None, Some('('), Some(')'), comment), None);
+        let string = highlighting::Type::String;
+        assert_eq!(edit(Some('('), None, None, string), None);
+        assert_eq!(edit(Some('"'), Some('x'), Some('"'), string), Some(PairEdit::StepOver));
+
+        let mut document =
+            Document::from_string("f() // \n\"a \"".to_string()).unwrap();
+        document.set_file_type(FileType::from("main.rs"));
+        let at = |x, y| Position { x, y };
+        assert_eq!(document.pair_edit(&at(3, 0), Some('(')), Some(PairEdit::Insert('(', ')')));
+        assert_eq!(document.pair_edit(&at(2, 0), Some(')')), Some(PairEdit::StepOver));
+        assert_eq!(document.pair_edit(&at(2, 0), None), Some(PairEdit::DeletePair));
+        assert_eq!(document.pair_edit(&at(7, 0), Some('(')), None);
+        assert_eq!(document.pair_edit(&at(3, 1), Some('(')), None);
+        assert_eq!(document.pair_edit(&at(3, 1), Some('"')), Some(PairEdit::StepOver));
+    }
+
+    #[test]
+    fn test_line_break() {
+        let document = Document::from_string("  let x = 1;\n  if x {\n  f()\n    ".to_string()).unwrap();
+        let at = |x, y| Position { x, y };
+        assert_eq!(document.line_break(&at(12, 0)), "\n  ");
+        assert_eq!(document.line_break(&at(8, 1)), "\n    ");
+        assert_eq!(document.line_break(&at(4, 2)), "\n    \n  ");
+        assert_eq!(document.line_break(&at(0, 4)), "\n");
+    }
+
+    #[test]
+    fn test_dedented() {
+        let document = Document::from_string("fn f() {\n    x\n    \n\t".to_string()).unwrap();
+        let at = |x, y| Position { x, y };
+        assert_eq!(document.dedented(&at(4, 2), '}'), Some(String::new()));
+        assert_eq!(document.dedented(&at(2, 2), ')'), Some(" ".to_string()));
+        assert_eq!(document.dedented(&at(1, 3), ']'), Some(String::new()));
+        assert_eq!(document.dedented(&at(4, 2), 'x'), None);
+        assert_eq!(document.dedented(&at(5, 1), '}'), None);
+        assert_eq!(document.dedented(&at(0, 2), '}'), None);
+    }
+
+    #[test]
+    fn test_substitute_word() {
+        let substitutions = Substitutions::parse("love = wuv\nthe = da\n");
+        let mut document = Document::from_string("i wove".to_string()).unwrap();
+        document.insert(&Position { x: 6, y: 0 }, ' ');
+        let cursor = document.substitute_word(
+            &Position { x: 6, y: 0 },
+            &Position { x: 7, y: 0 },
+            &substitutions,
+        );
+        assert_eq!(cursor, Position { x: 6, y: 0 });
+        assert_eq!(String::from_utf8(document.contents()).unwrap(), "i wuv \n");
+        document.undo();
+        assert_eq!(String::from_utf8(document.contents()).unwrap(), "i wove \n");
+        let cursor = document.substitute_word(
+            &Position { x: 1, y: 0 },
+            &Position { x: 2, y: 0 },
+            &substitutions,
+        );
+        assert_eq!(cursor, Position { x: 2, y: 0 });
+        assert_eq!(String::from_utf8(document.contents()).unwrap(), "i wove \n");
+    }
+
+    #[test]
+    fn test_incremental_stats() {
+        let mut document = Document::from_string("Hewwo!\n\nUwU".to_string()).unwrap();
+        assert_eq!(document.stats().paragraphs, 2);
+        document.insert(&Position { x: 0, y: 1 }, 'a');
+        document.insert(&Position { x: 3, y: 2 }, '\n');
+        document.delete(&Position { x: 6, y: 0 });
+        assert_eq!(document.stats(), document.rows_stats(0, document.len()));
+        assert_eq!(document.stats().paragraphs, 1);
+        assert_eq!(document.session_words(), 1);
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut document = Document::from_string("hewwo".to_string()).unwrap();
+        document.insert_str(&Position { x: 5, y: 0 }, " (◕‿◕)");
+        document.commit();
+        document.insert(&Position { x: 2, y: 0 }, '\n');
+        document.delete(&Position { x: 0, y: 1 });
+        document.insert(&Position { x: 0, y: 2 }, '!');
+        document.commit();
+        assert_eq!(document.row(1).unwrap().as_bytes(), "wo (◕‿◕)".as_bytes());
+        assert_eq!(document.len(), 3);
+
+        assert_eq!(document.undo().map(|at| (at.x, at.y)), Some((2, 0)));
+        assert_eq!(document.len(), 1);
+        assert_eq!(document.row(0).unwrap().as_bytes(), "hewwo (◕‿◕)".as_bytes());
+        document.undo();
+        assert_eq!(document.row(0).unwrap().as_bytes(), b"hewwo");
+        assert!(document.undo().is_none());
+        assert_eq!(document.stats(), document.rows_stats(0, document.len()));
+    }
+
+    #[test]
+    fn test_changed_on_disk() {
+        let path = std::env::temp_dir().join(format!("kyun-disk-{}.txt", std::process::id()));
+        let file_name = path.to_string_lossy().to_string();
+        fs::write(&path, "hewwo\n").unwrap();
+        let mut document = Document::open(&file_name).unwrap();
+        assert!(!document.changed_on_disk());
+        fs::write(&path, "hewwo\n").unwrap();
+        assert!(!document.changed_on_disk());
+        fs::write(&path, "hewwo wowwd\n").unwrap();
+        assert!(document.changed_on_disk());
+        document.reload().unwrap();
+        assert!(!document.changed_on_disk());
+        assert_eq!(document.row(0).unwrap().as_bytes(), b"hewwo wowwd");
+        fs::remove_file(&path).unwrap();
+    }
 }
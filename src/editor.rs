@@ -1,32 +1,58 @@
+use crate::browser::{EntryKind, FileBrowser};
+use crate::cli::Args;
+use crate::command::{self, Command};
+use crate::completion;
+use crate::diff::{self, Change};
+use crate::config::Config;
+use crate::goto::{self, Target};
+use crate::highlighting;
+use crate::kaomoji::Catalogue;
+use crate::picker::Picker;
+use crate::prompt::{self, History, LineEditor, PromptKind};
+use crate::statusbar::{self, StatusInfo, StatusTemplate};
+use crate::substitutions::{self, Substitutions};
+use crate::swap;
+use crate::uwu::{self, Uwuifier};
+use crate::Dictionary;
 use crate::Document;
+use crate::FileType;
+use crate::PairEdit;
+use crate::Position;
 use crate::Row;
+use crate::SearchDirection;
+use crate::Snippets;
 use crate::Terminal;
+use crate::Theme;
+use crate::UwuScope;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::io::{self, Read};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::time::Instant;
 use std::include_bytes;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 use crossterm::{
-    style::{Colors, Color},
     event::{Event, KeyCode, KeyModifiers, KeyEvent, read},
+    style::{Attribute, Color},
 };
 
-const STATUS_FG_COLOR: Color = Color::Rgb{r: 252, g: 196, b: 228};
-const STATUS_BG_COLOR: Color = Color::Rgb{r: 153, g: 1, b: 87};
 const QUIT_TIMES: u8 = 3;
 const WELCOME_WIDTH : usize = 41;
-#[derive(PartialEq, Copy, Clone)]
-pub enum SearchDirection {
-    Forward,
-    Backward,
-}
-
-#[derive(Default, Clone)]
-pub struct Position {
-    pub x: usize,
-    pub y: usize,
-}
-
+const STATISTICS_WIDTH: usize = 23;
+const PICKER_WIDTH: usize = 40;
+const PICKER_HEIGHT: usize = 8;
+const TICK: Duration = Duration::from_millis(250);
+const FLASH_DURATION: Duration = Duration::from_millis(800);
+const SWAP_INTERVAL: Duration = Duration::from_secs(2);
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// Errors in a row after which kyun gives up on the terminal.
+const MAX_FAILURES: usize = 10;
+const READ_ONLY_MESSAGE: &str = "This fiwe is wead-onwy (´・ω・`)";
+const LARGE_FILE_MESSAGE: &str = "Big fiwe! It's wead-onwy untiw you stawt typing (⊙_⊙;)";
 struct StatusMessage {
     text: String,
     time: Instant,
@@ -50,54 +76,214 @@ pub struct Editor {
     status_message: StatusMessage,
     quit_times: u8,
     highlighted_word: Option<String>,
+    theme: Theme,
+    status_template: StatusTemplate,
+    git_branch: Option<String>,
+    selection_anchor: Option<Position>,
+    show_statistics: bool,
+    dictionary: Option<Dictionary>,
+    /// Tells transformed words apart for the statistics when spell
+    /// checking, and with it `dictionary`, is off.
+    statistics_dictionary: Option<Dictionary>,
+    kaomoji: Catalogue,
+    /// The tab stops of the last expanded snippet still to come, and the
+    /// length of their text.
+    tab_stops: Vec<(Position, usize)>,
+    /// The placeholder text the cursor was put after, selected, which
+    /// typing replaces.
+    placeholder: Option<(Position, usize)>,
+    picker: Option<Picker>,
+    tab_width: usize,
+    flashed_row: Option<(usize, Instant)>,
+    histories: HashMap<PromptKind, History>,
+    browser: Option<FileBrowser>,
+    swapped_revision: usize,
+    last_swap: Instant,
+    /// Swap files recovery was already offered for.
+    recovery_offered: HashSet<PathBuf>,
+    /// Swap files this session wrote or recovered, which go on exit. Any
+    /// other one may hold a crashed session's work.
+    own_swaps: HashSet<PathBuf>,
+    last_disk_check: Instant,
+    /// Set when the user chose to keep the buffer over a changed file.
+    disk_change_ignored: bool,
+    /// Whether files are opened read-only, as with `--readonly`.
+    read_only: bool,
+    /// Whether typed characters go through the uwu rules.
+    uwu: bool,
+    uwuifier: Uwuifier,
+    /// Uwu scopes overriding the file types' own, by file type name.
+    uwu_scopes: HashMap<String, UwuScope>,
+    /// The other open files, in the order after the current one.
+    buffers: Vec<Buffer>,
+    buffer_index: usize,
+}
+
+/// A document that is open but not shown.
+struct Buffer {
+    document: Document,
+    cursor_position: Position,
+    offset: Position,
 }
 
 impl Editor {
     pub fn run(&mut self) {
+        if self.browser.is_none() {
+            self.offer_recovery();
+        }
+        let mut failures = 0;
         loop {
-            if let Err(error) = self.refresh_screen() {
-                die(error);
-            }
+            let mut result = self.refresh_screen();
             if self.should_quit {
                 break;
             }
-            if let Err(error) = self.process_keypress() {
-                die(error);
+            if result.is_ok() {
+                result = self.process_keypress();
             }
+            match result {
+                Ok(()) => failures = 0,
+                Err(error) => {
+                    failures += 1;
+                    if failures >= MAX_FAILURES {
+                        self.save_swap();
+                        die(&error);
+                    }
+                    self.status_message = StatusMessage::from(format!("EWWOR: {} (｡•́︿•̀｡)", error));
+                }
+            }
+            self.write_swap();
+            self.check_disk();
+        }
+        for path in &self.own_swaps {
+            swap::remove(path);
         }
     }
-    pub fn default() -> Self {
-        let args: Vec<String> = env::args().collect();
+    pub fn new(args: &Args, config: &Config) -> Result<Self, std::io::Error> {
         let mut initial_status =
             String::from("HEWP: Ctrl-F = find | Ctrl-S = save | Esc = qwit");
 
-        let document = if let Some(file_name) = args.get(1) {
-            let doc = Document::open(file_name);
-            if let Ok(doc) = doc {
+        let mut browser = None;
+        let mut buffers = Vec::new();
+        for file in &args.files {
+            let mut document = if file.name == "-" {
+                // Keys are still read from the terminal: crossterm opens
+                // /dev/tty itself when stdin is not one.
+                let mut contents = String::new();
+                io::stdin().read_to_string(&mut contents)?;
+                Document::from_string(contents)?
+            } else if Path::new(&file.name).is_dir() {
+                if browser.is_none() {
+                    match FileBrowser::open(Path::new(&file.name)) {
+                        Ok(opened) => browser = Some(opened),
+                        Err(error) => {
+                            initial_status = format!("EWWOR!!! Could not wist {}: {}", file.name, error);
+                        }
+                    }
+                }
+                continue;
+            } else if let Ok(doc) = Document::open(&file.name) {
+                if doc.is_large() {
+                    initial_status = LARGE_FILE_MESSAGE.to_string();
+                }
                 doc
             } else {
-                initial_status = format!("EWWOR!!! Could not open fiwe??! ＼＼(๑`^´๑)۶/怒／／ {}", file_name);
-                Document::default()
+                initial_status = format!("EWWOR!!! Could not open fiwe??! ＼＼(๑`^´๑)۶/怒／／ {}", file.name);
+                continue;
+            };
+            if let Some(file_type) = args.file_type.as_deref().and_then(FileType::from_name) {
+                document.set_file_type(file_type);
+            }
+            if args.read_only {
+                document.set_read_only(true);
+            }
+            let (y, x) = file.target.as_ref().map_or((0, None), |target| {
+                target.resolve(&Position::default(), document.len())
+            });
+            let x = x.unwrap_or(0).min(document.row(y).map_or(0, Row::len));
+            buffers.push(Buffer {
+                document,
+                cursor_position: Position { x, y },
+                offset: Position::default(),
+            });
+        }
+        let current = if buffers.is_empty() {
+            Buffer {
+                document: Document::default(),
+                cursor_position: Position::default(),
+                offset: Position::default(),
             }
         } else {
-            Document::default()
+            buffers.remove(0)
+        };
+        let document = current.document;
+
+        let mut terminal = Terminal::default()?;
+        if let Some(color_depth) = config.color_depth {
+            terminal.set_color_depth(color_depth);
+        }
+        let theme = if let Some(theme) = Theme::load(&config.theme, terminal.color_depth()) {
+            theme
+        } else {
+            initial_status = format!("Thewe is no theme cawwed {} (´•ω•̥`)", config.theme);
+            Theme::load("pastel", terminal.color_depth()).unwrap_or_default()
         };
 
         let welcome_bytes = include_bytes!("welcome.txt");
         let welcome_string = String::from_utf8(welcome_bytes.to_vec()).unwrap();
         let welcome = Document::from_string(welcome_string).unwrap();
+        let mut uwuifier = Uwuifier::new(args.intensity.unwrap_or(config.uwu_intensity), uwu::Rng::seed());
+        uwuifier.substitutions = Substitutions::load();
+        uwuifier.snippets = Snippets::load();
 
-        Self {
+        let mut editor = Self {
             should_quit: false,
-            terminal: Terminal::default().expect("Failed to initialize terminal"),
-            document,
+            terminal,
             cursor_position: Position::default(),
             offset: Position::default(),
             status_message: StatusMessage::from(initial_status),
             welcome_message: welcome,
             quit_times: QUIT_TIMES,
             highlighted_word: None,
+            theme,
+            status_template: StatusTemplate::parse(&config.status_left, &config.status_right),
+            git_branch: statusbar::git_branch(document.file_name.as_deref()),
+            document,
+            selection_anchor: None,
+            show_statistics: false,
+            dictionary: if config.spell_check {
+                Some(Dictionary::load())
+            } else {
+                None
+            },
+            statistics_dictionary: None,
+            kaomoji: Catalogue::load(),
+            tab_stops: Vec::new(),
+            placeholder: None,
+            picker: None,
+            tab_width: config.tab_width,
+            flashed_row: None,
+            histories: HashMap::new(),
+            browser,
+            swapped_revision: 0,
+            last_swap: Instant::now(),
+            recovery_offered: HashSet::new(),
+            own_swaps: HashSet::new(),
+            last_disk_check: Instant::now(),
+            disk_change_ignored: false,
+            read_only: args.read_only,
+            uwu: !args.no_uwu,
+            uwuifier,
+            uwu_scopes: config.uwu_scopes.clone(),
+            buffers,
+            buffer_index: 0,
+        };
+        if current.cursor_position.y > 0 || current.cursor_position.x > 0 {
+            editor.goto(&Target::Line {
+                line: current.cursor_position.y + 1,
+                column: Some(current.cursor_position.x + 1),
+            });
         }
+        Ok(editor)
     }
 
     fn refresh_screen(&mut self) -> Result<(), std::io::Error> {
@@ -107,6 +293,8 @@ impl Editor {
         if self.should_quit {
             Terminal::quit();
         } else {
+            self.document
+                .load_window(self.offset.y, self.terminal.size().height as usize)?;
             self.document.highlight(
                 &self.highlighted_word,
                 Some(
@@ -114,56 +302,575 @@ impl Editor {
                         .y
                         .saturating_add(self.terminal.size().height as usize),
                 ),
+                self.dictionary.as_ref(),
             );
-            self.draw_rows();
+            if let Some(browser) = &self.browser {
+                self.draw_browser(browser);
+            } else {
+                self.draw_rows();
+            }
             self.draw_status_bar();
             self.draw_message_bar();
+            if self.show_statistics {
+                let transformed_words = match self.dictionary.as_ref().or(self.statistics_dictionary.as_ref()) {
+                    Some(dictionary) => self.document.transformed_words(dictionary),
+                    None => 0,
+                };
+                self.draw_statistics(transformed_words);
+            }
+            if let Some(picker) = &self.picker {
+                self.draw_picker(picker);
+            }
 
-            Terminal::cursor_position(&Position {
-                x: self.cursor_position.x.saturating_sub(self.offset.x),
-                y: self.cursor_position.y.saturating_sub(self.offset.y),
-            });
+            if let Some(browser) = &self.browser {
+                let height = self.terminal.size().height as usize;
+                Terminal::cursor_position(&Position {
+                    x: 0,
+                    y: browser.selected - browser.first_visible(height),
+                });
+            } else {
+                let x = self.document.row(self.cursor_position.y).map_or(0, |row| {
+                    row.columns(self.offset.x, self.cursor_position.x, self.tab_width)
+                });
+                Terminal::cursor_position(&Position {
+                    x,
+                    y: self.cursor_position.y.saturating_sub(self.offset.y),
+                });
+            }
         }
         Terminal::cursor_show();
         Terminal::flush()
     }
     fn save(&mut self) {
+        if self.document.is_read_only() {
+            self.status_message = StatusMessage::from(READ_ONLY_MESSAGE.to_string());
+            return;
+        }
+        let old_swap = swap::path(self.document.file_name.as_deref());
         if self.document.file_name.is_none() {
-            let new_name = self.prompt("Sawe as: ", |_, _, _| {}).unwrap_or(None);
+            let new_name = self
+                .prompt("Sawe as: ", PromptKind::SaveAs, |_, _, _| {})
+                .unwrap_or(None);
             if new_name.is_none() {
                 self.status_message = StatusMessage::from("Sawe aborted ; w ;.".to_string());
                 return;
             }
             self.document.file_name = new_name;
         }
+        if self.document.changed_on_disk() {
+            let disk = self.disk_contents();
+            let buffer = String::from_utf8_lossy(&self.document.contents()).to_string();
+            let answer = self.ask_with_diff(
+                "Fiwe changed on disk! o = ovewwwite, r = wewoad, d = diff, Enter = cancew: ",
+                "disk -> buffew",
+                &disk,
+                &buffer,
+            );
+            match answer.as_deref() {
+                Some("o") => (),
+                Some("r") => {
+                    self.reload();
+                    return;
+                }
+                _ => {
+                    self.status_message = StatusMessage::from("Sawe aborted ; w ;.".to_string());
+                    return;
+                }
+            }
+        }
 
         if self.document.save().is_ok() {
+            for path in old_swap.iter().chain(&swap::path(self.document.file_name.as_deref())) {
+                swap::remove(path);
+            }
+            self.disk_change_ignored = false;
+            self.git_branch = statusbar::git_branch(self.document.file_name.as_deref());
             self.status_message = StatusMessage::from("Fiwe sawed successfuwwy. (- w -)ゞ".to_string());
         } else {
             self.status_message = StatusMessage::from("Error writing file! OWO".to_string());
         }
     }
+    fn command_line(&mut self) {
+        let line = self
+            .prompt(":", PromptKind::Command, |_, key, line| {
+                if key.code != KeyCode::Tab {
+                    return;
+                }
+                if let Some((name, argument)) = line.text().split_once(' ') {
+                    if let Some(completed) = prompt::complete_path(argument.trim_start()) {
+                        line.set_text(&format!("{} {}", name, completed));
+                    }
+                } else {
+                    let candidates = command::complete(line.text());
+                    let mut completed = command::common_prefix(&candidates);
+                    if candidates.len() == 1 {
+                        completed.push(' ');
+                    }
+                    line.set_text(&completed);
+                }
+            })
+            .unwrap_or(None);
+        if let Some(line) = line {
+            match command::parse(&line) {
+                Ok(command) => {
+                    if let Err(error) = self.execute(command) {
+                        self.status_message = StatusMessage::from(format!("EWWOR: {} (｡•́︿•̀｡)", error));
+                    }
+                }
+                Err(error) => {
+                    self.status_message = StatusMessage::from(format!("EWWOR: {} (｡•́︿•̀｡)", error));
+                }
+            }
+        }
+    }
+    fn execute(&mut self, command: Command) -> Result<(), String> {
+        match command {
+            Command::Write(file_name) => {
+                if file_name.is_some() {
+                    self.document.file_name = file_name;
+                }
+                self.save();
+            }
+            Command::Edit(file_name) => {
+                if self.document.is_dirty() {
+                    return Err("Fiwe has unsawed changes, :w fiwst".to_string());
+                }
+                self.open(&file_name)?;
+            }
+            Command::Quit { force } => {
+                if self.has_unsaved_changes() && !force {
+                    return Err("Fiwe has unsawed changes, use :q! to qwit anyway".to_string());
+                }
+                self.should_quit = true;
+            }
+            Command::Goto(target) => self.goto(&target),
+            Command::NextBuffer => self.switch_buffer(true),
+            Command::PreviousBuffer => self.switch_buffer(false),
+            Command::Set { option, value } => match option.as_str() {
+                "tabwidth" => {
+                    self.tab_width = value
+                        .parse()
+                        .ok()
+                        .filter(|width| *width > 0)
+                        .ok_or_else(|| format!("{} is not a tab width", value))?;
+                }
+                "uwu" => {
+                    self.uwu = match value.as_str() {
+                        "on" | "true" => true,
+                        "off" | "false" => false,
+                        _ => return Err(format!("{} is not on ow off", value)),
+                    };
+                }
+                "intensity" => {
+                    self.uwuifier.intensity = uwu::Intensity::from_name(&value).ok_or_else(|| {
+                        format!("{} is not wight, medium ow maximum", value)
+                    })?;
+                }
+                "uwuscope" => {
+                    let scope = UwuScope::from_name(&value).ok_or_else(|| {
+                        format!("{} is not evewywhewe, pwose ow nowhewe", value)
+                    })?;
+                    self.uwu_scopes.insert(self.document.file_type(), scope);
+                }
+                _ => return Err(format!("Unknown option: {}", option)),
+            },
+            Command::FileType(name) => {
+                let file_type = FileType::from_name(&name)
+                    .ok_or_else(|| format!("Unknown fiwe type: {}", name))?;
+                self.document.set_file_type(file_type);
+            }
+            Command::Uwuify => {
+                for y in 0..self.document.len() {
+                    let row = if let Some(row) = self.document.row(y) {
+                        row
+                    } else {
+                        break;
+                    };
+                    let line = String::from_utf8_lossy(row.as_bytes()).to_string();
+                    let len = row.len();
+                    self.document.replace(&Position { x: 0, y }, len, &uwu::uwuify_with(&line, &mut self.uwuifier));
+                }
+                self.move_cursor(KeyCode::Null);
+            }
+            Command::Deuwuify => self.deuwuify()?,
+            Command::Browse(dir) => {
+                let dir = dir.map_or_else(|| self.current_dir(), PathBuf::from);
+                self.browse(&dir)?;
+            }
+        }
+        Ok(())
+    }
+    /// Moves the cursor to `target`, centers it on the screen and flashes
+    /// the row it landed on.
+    fn goto(&mut self, target: &Target) {
+        let (y, column) = target.resolve(&self.cursor_position, self.document.len());
+        let row_len = self.document.row(y).map_or(0, Row::len);
+        self.cursor_position = Position {
+            x: column.unwrap_or(0).min(row_len),
+            y,
+        };
+        let height = self.terminal.size().height as usize;
+        self.offset.y = y.saturating_sub(height / 2);
+        self.scroll();
+        self.flashed_row = Some((y, Instant::now()));
+    }
+    fn open(&mut self, file_name: &str) -> Result<(), String> {
+        if Path::new(file_name).is_dir() {
+            return self.browse(Path::new(file_name));
+        }
+        let mut document = Document::open(file_name)
+            .map_err(|error| format!("Could not open {}: {}", file_name, error))?;
+        if self.read_only {
+            document.set_read_only(true);
+        }
+        self.document = document;
+        self.cursor_position = Position::default();
+        self.offset = Position::default();
+        self.git_branch = statusbar::git_branch(self.document.file_name.as_deref());
+        self.browser = None;
+        self.disk_change_ignored = false;
+        self.tab_stops.clear();
+        if self.document.is_large() {
+            self.status_message = StatusMessage::from(LARGE_FILE_MESSAGE.to_string());
+        }
+        self.offer_recovery();
+        Ok(())
+    }
+    /// Writes the swap file if the document changed since it was last
+    /// written, at most every `SWAP_INTERVAL`.
+    fn write_swap(&mut self) {
+        if self.document.revision() != self.swapped_revision
+            && self.last_swap.elapsed() >= SWAP_INTERVAL
+        {
+            self.save_swap();
+        }
+    }
+    fn save_swap(&mut self) {
+        let revision = self.document.revision();
+        if !self.document.is_dirty() {
+            return;
+        }
+        if let Some(path) = swap::path(self.document.file_name.as_deref()) {
+            if swap::write(&path, &self.document.contents()).is_err() {
+                self.status_message = StatusMessage::from("Could not wwite the swap fiwe! OWO".to_string());
+            }
+            self.own_swaps.insert(path);
+        }
+        self.swapped_revision = revision;
+        self.last_swap = Instant::now();
+    }
+    /// Asks what to do with a swap file left behind by a crash, once per
+    /// file and session.
+    fn offer_recovery(&mut self) {
+        let path = match swap::newer(self.document.file_name.as_deref()) {
+            Some(path) if self.recovery_offered.insert(path.clone()) => path,
+            _ => return,
+        };
+        let recovered = if let Ok(recovered) = fs::read_to_string(&path) {
+            recovered
+        } else {
+            return;
+        };
+        let saved = String::from_utf8_lossy(&self.document.contents()).to_string();
+        let answer = self.ask_with_diff(
+            "Found unsawed changes fwom a cwash! r = wecovew, d = diff, x = dewete, Enter = ignowe: ",
+            "sawed -> swap",
+            &saved,
+            &recovered,
+        );
+        match answer.as_deref() {
+            Some("r") => {
+                self.document.recover(&recovered);
+                self.cursor_position = Position::default();
+                self.offset = Position::default();
+                self.status_message = StatusMessage::from("Wecovewed! Sawe to keep it (ﾉ´ヮ`)ﾉ*: ･ﾟ".to_string());
+                self.own_swaps.insert(path);
+            }
+            Some("x") => swap::remove(&path),
+            _ => (),
+        }
+    }
+    /// Asks `question` until the answer is something other than `d`, which
+    /// shows how `new` differs from `old`.
+    fn ask_with_diff(&mut self, question: &str, title: &str, old: &str, new: &str) -> Option<String> {
+        let answer = loop {
+            let answer = self
+                .prompt(question, PromptKind::Other, |editor, key, _| {
+                    if let Some(picker) = &mut editor.picker {
+                        match key.code {
+                            KeyCode::Up => picker.select_previous(),
+                            KeyCode::Down => picker.select_next(),
+                            _ => (),
+                        }
+                    }
+                })
+                .unwrap_or(None);
+            if answer.as_deref() != Some("d") {
+                break answer;
+            }
+            let mut lines: Vec<String> = diff::lines(old, new)
+                .into_iter()
+                .filter_map(|change| match change {
+                    Change::Same(_) => None,
+                    Change::Removed(line) => Some(format!("- {}", line)),
+                    Change::Added(line) => Some(format!("+ {}", line)),
+                    Change::Replaced(removed, added) => {
+                        Some(format!("~ wepwaced {} wines with {}", removed, added))
+                    }
+                })
+                .collect();
+            if lines.is_empty() {
+                lines.push("no diffewences".to_string());
+            }
+            self.picker = Some(Picker::new(title, lines));
+        };
+        self.picker = None;
+        answer
+    }
+    fn disk_contents(&self) -> String {
+        self.document
+            .file_name
+            .as_ref()
+            .and_then(|file_name| fs::read(file_name).ok())
+            .map(|contents| String::from_utf8_lossy(&contents).to_string())
+            .unwrap_or_default()
+    }
+    /// Offers to reload an unmodified buffer whose file changed on disk.
+    fn check_disk(&mut self) {
+        if self.document.is_dirty()
+            || self.disk_change_ignored
+            || self.last_disk_check.elapsed() < DISK_CHECK_INTERVAL
+        {
+            return;
+        }
+        self.last_disk_check = Instant::now();
+        if !self.document.changed_on_disk() {
+            return;
+        }
+        let disk = self.disk_contents();
+        let buffer = String::from_utf8_lossy(&self.document.contents()).to_string();
+        let answer = self.ask_with_diff(
+            "Fiwe changed on disk. y = wewoad, d = diff, n = keep this vewsion: ",
+            "buffew -> disk",
+            &buffer,
+            &disk,
+        );
+        if answer.as_deref() == Some("y") {
+            self.reload();
+        } else {
+            self.disk_change_ignored = true;
+        }
+    }
+    fn reload(&mut self) {
+        if let Err(error) = self.document.reload() {
+            self.status_message = StatusMessage::from(format!("Could not wewoad: {} (｡•́︿•̀｡)", error));
+            return;
+        }
+        self.cursor_position.y = self.cursor_position.y.min(self.document.len());
+        self.move_cursor(KeyCode::Null);
+        self.scroll();
+        self.disk_change_ignored = false;
+        self.status_message = StatusMessage::from("Wewoaded fwom disk (｡･ω･)ﾉﾞ".to_string());
+    }
+    fn browse(&mut self, dir: &Path) -> Result<(), String> {
+        let browser = FileBrowser::open(dir)
+            .map_err(|error| format!("Could not wist {}: {}", dir.display(), error))?;
+        self.browser = Some(browser);
+        Ok(())
+    }
+    /// The directory of the open file, or the working directory.
+    fn current_dir(&self) -> PathBuf {
+        self.document
+            .file_name
+            .as_ref()
+            .and_then(|file_name| Path::new(file_name).parent())
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+    }
+    /// Handles a key while the file browser is shown. Returns false for keys
+    /// that should reach the editor as usual.
+    fn browser_keypress(&mut self, key: KeyEvent) -> bool {
+        let height = self.terminal.size().height as usize;
+        let browser = if let Some(browser) = &mut self.browser {
+            browser
+        } else {
+            return false;
+        };
+        let result = match (key.modifiers, key.code) {
+            (KeyModifiers::CONTROL, KeyCode::Char('q'))
+            | (KeyModifiers::CONTROL, KeyCode::Char('e')) => return false,
+            (_, KeyCode::Up) => {
+                browser.select_previous();
+                Ok(())
+            }
+            (_, KeyCode::Down) => {
+                browser.select_next();
+                Ok(())
+            }
+            (_, KeyCode::PageUp) => {
+                browser.selected = browser.selected.saturating_sub(height);
+                Ok(())
+            }
+            (_, KeyCode::PageDown) => {
+                browser.selected = (browser.selected + height).min(browser.entries.len().saturating_sub(1));
+                Ok(())
+            }
+            (_, KeyCode::Home) => {
+                browser.selected = 0;
+                Ok(())
+            }
+            (_, KeyCode::End) => {
+                browser.selected = browser.entries.len().saturating_sub(1);
+                Ok(())
+            }
+            (_, KeyCode::Enter) | (_, KeyCode::Right) => self.open_selected(),
+            (_, KeyCode::Left) | (_, KeyCode::Backspace) => {
+                let parent = browser.dir.join("..");
+                browser.change_dir(&parent).map_err(|error| error.to_string())
+            }
+            (_, KeyCode::Char('.')) => browser.toggle_hidden().map_err(|error| error.to_string()),
+            (_, KeyCode::Char('n')) => self.create_file(),
+            (_, KeyCode::Char('r')) => self.rename_file(),
+            (_, KeyCode::Char('d')) => self.delete_file(),
+            (_, KeyCode::Esc) | (_, KeyCode::Char('q')) => {
+                self.browser = None;
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        if let Err(error) = result {
+            self.status_message = StatusMessage::from(format!("EWWOR: {} (｡•́︿•̀｡)", error));
+        }
+        true
+    }
+    fn open_selected(&mut self) -> Result<(), String> {
+        let browser = if let Some(browser) = &mut self.browser {
+            browser
+        } else {
+            return Ok(());
+        };
+        let (path, kind) = match (browser.selected_path(), browser.selection()) {
+            (Some(path), Some(entry)) => (path, entry.kind),
+            _ => return Ok(()),
+        };
+        if kind == EntryKind::Directory {
+            return browser.change_dir(&path).map_err(|error| error.to_string());
+        }
+        if self.document.is_dirty() {
+            return Err("Fiwe has unsawed changes, :w fiwst".to_string());
+        }
+        self.open(&path.to_string_lossy())
+    }
+    fn create_file(&mut self) -> Result<(), String> {
+        let name = self
+            .prompt("New fiwe (end with / fow a diwectowy): ", PromptKind::Other, |_, _, _| {})
+            .unwrap_or(None);
+        let (name, browser) = match (name, &mut self.browser) {
+            (Some(name), Some(browser)) => (name, browser),
+            _ => return Ok(()),
+        };
+        let path = browser.dir.join(&name);
+        let created = if name.ends_with('/') {
+            fs::create_dir_all(&path)
+        } else {
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .map(|_| ())
+        };
+        created.map_err(|error| format!("Could not cweate {}: {}", name, error))?;
+        browser.refresh().map_err(|error| error.to_string())?;
+        browser.select(name.trim_end_matches('/'));
+        Ok(())
+    }
+    fn rename_file(&mut self) -> Result<(), String> {
+        let old_name = match self.browser.as_ref().and_then(FileBrowser::selection) {
+            Some(entry) if entry.name != ".." => entry.name.clone(),
+            _ => return Ok(()),
+        };
+        let new_name = self
+            .prompt(&format!("Wename {} to: ", old_name), PromptKind::Other, |_, _, _| {})
+            .unwrap_or(None);
+        let (new_name, browser) = match (new_name, &mut self.browser) {
+            (Some(new_name), Some(browser)) => (new_name, browser),
+            _ => return Ok(()),
+        };
+        let from = browser.dir.join(&old_name);
+        let to = browser.dir.join(&new_name);
+        if to.exists() {
+            return Err(format!("{} awweady exists", new_name));
+        }
+        fs::rename(&from, &to).map_err(|error| format!("Could not wename {}: {}", old_name, error))?;
+        browser.refresh().map_err(|error| error.to_string())?;
+        browser.select(&new_name);
+        // Keep saving to the right place if the open file was renamed.
+        let renamed_open_file = self
+            .document
+            .file_name
+            .as_ref()
+            .and_then(|file_name| fs::canonicalize(file_name).ok())
+            .is_some_and(|open| open == from);
+        if renamed_open_file {
+            self.document.file_name = Some(to.to_string_lossy().to_string());
+        }
+        Ok(())
+    }
+    fn delete_file(&mut self) -> Result<(), String> {
+        let (name, path, kind) = match self.browser.as_ref().map(|browser| (browser.selection(), browser.selected_path())) {
+            Some((Some(entry), Some(path))) if entry.name != ".." => (entry.name.clone(), path, entry.kind),
+            _ => return Ok(()),
+        };
+        let answer = self
+            .prompt(&format!("Weawwy dewete {}? (y/n): ", name), PromptKind::Other, |_, _, _| {})
+            .unwrap_or(None);
+        if answer.as_deref() != Some("y") {
+            self.status_message = StatusMessage::from("Nothing deweted (*´▽`*)".to_string());
+            return Ok(());
+        }
+        let deleted = if kind == EntryKind::Directory {
+            fs::remove_dir(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        deleted.map_err(|error| format!("Could not dewete {}: {}", name, error))?;
+        if let Some(browser) = &mut self.browser {
+            browser.refresh().map_err(|error| error.to_string())?;
+        }
+        self.status_message = StatusMessage::from(format!("{} is gone (；ω；)ノ", name));
+        Ok(())
+    }
     fn search(&mut self) {
+        if self.document.is_large() {
+            self.status_message = StatusMessage::from(
+                "Seawching needs the whowe fiwe, stawt typing to woad it (・・;)".to_string(),
+            );
+            return;
+        }
         let old_position = self.cursor_position.clone();
         let mut direction = SearchDirection::Forward;
         let query = self
             .prompt(
-                "Searching owo (ESC to cancel, Awwows to nawigate): ",
+                "Searching owo (ESC to cancel, Ctrl-N/Ctrl-P to nawigate): ",
+                PromptKind::Search,
                 |editor, key, query| {
+                    let query = query.text();
                     let mut moved = false;
-                    match key.code {
-                        KeyCode::Right | KeyCode::Down => {
+                    match (key.modifiers, key.code) {
+                        (KeyModifiers::CONTROL, KeyCode::Char('n')) => {
                             direction = SearchDirection::Forward;
                             editor.move_cursor(KeyCode::Right);
                             moved = true;
                         }
-                        KeyCode::Left | KeyCode::Up => direction = SearchDirection::Backward,
+                        (KeyModifiers::CONTROL, KeyCode::Char('p')) => {
+                            direction = SearchDirection::Backward;
+                        }
                         _ => direction = SearchDirection::Forward,
                     }
                     if let Some(position) =
                         editor
                             .document
-                            .find(&query, &editor.cursor_position, direction)
+                            .find(query, &editor.cursor_position, direction)
                     {
                         editor.cursor_position = position;
                         editor.scroll();
@@ -181,13 +888,340 @@ impl Editor {
         }
         self.highlighted_word = None;
     }
+    fn pick_kaomoji(&mut self) {
+        let faces = |catalogue: &Catalogue, query: &str| {
+            catalogue
+                .search(query)
+                .iter()
+                .map(|entry| entry.face.clone())
+                .collect()
+        };
+        self.picker = Some(Picker::new("kaomoji", faces(&self.kaomoji, "")));
+        let mut confirmed = false;
+        self.prompt(
+            "Kaomoji (type a mood, Up/Down to pick): ",
+            PromptKind::Other,
+            |editor, key, query| match key.code {
+                KeyCode::Enter => confirmed = true,
+                KeyCode::Up => {
+                    if let Some(picker) = &mut editor.picker {
+                        picker.select_previous();
+                    }
+                }
+                KeyCode::Down => {
+                    if let Some(picker) = &mut editor.picker {
+                        picker.select_next();
+                    }
+                }
+                _ => {
+                    let items = faces(&editor.kaomoji, query.text());
+                    if let Some(picker) = &mut editor.picker {
+                        picker.set_items(items);
+                    }
+                }
+            },
+        )
+        .ok();
+        let face = self.picker.take().and_then(|picker| picker.selection().cloned());
+        if let (true, Some(face)) = (confirmed, face) {
+            self.document.insert_str(&self.cursor_position, &face);
+            self.cursor_position.x += face.graphemes(true).count();
+            self.kaomoji.mark_used(&face);
+        }
+    }
+    /// Inserts the closing half along with the opening half of a pair, or
+    /// steps over a closing half that is already there. Returns whether `c`
+    /// was taken care of.
+    fn type_pair(&mut self, c: char) -> bool {
+        let at = self.cursor_position.clone();
+        match self.document.pair_edit(&at, Some(c)) {
+            Some(PairEdit::Insert(open, close)) => {
+                self.document.insert_str(&at, &format!("{}{}", open, close));
+            }
+            Some(PairEdit::StepOver) => (),
+            _ => return false,
+        }
+        self.move_cursor(KeyCode::Right);
+        if self.uwu && self.uwu_applies(c) {
+            self.substitute_word(&at);
+        }
+        true
+    }
+    /// Inserts `c` at the cursor as half of a pair, or else through the uwu
+    /// rules if they apply.
+    fn type_char(&mut self, c: char) {
+        if self.type_pair(c) {
+            return;
+        }
+        self.dedent(c);
+        if !self.uwu || !self.uwu_applies(c) {
+            self.document.insert(&self.cursor_position, c);
+            self.move_cursor(KeyCode::Right);
+            return;
+        }
+        let at = self.cursor_position.clone();
+        let Position { x, y } = at;
+        // The rules look at most two characters back.
+        let before: Vec<char> = (x.saturating_sub(2)..x)
+            .filter_map(|x| self.document.row(y)?.grapheme(x))
+            .flat_map(str::chars)
+            .collect();
+        let expansion = self.uwuifier.transform(&before, c);
+        self.document.insert_str(&self.cursor_position, &expansion.text);
+        for _ in 0..expansion.cursor {
+            self.move_cursor(KeyCode::Right);
+        }
+        if substitutions::is_word_boundary(c) {
+            self.substitute_word(&at);
+        }
+    }
+    /// Breaks the line at the cursor, indented the way
+    /// [`Document::line_break`] says.
+    fn insert_newline(&mut self) {
+        let text = self.document.line_break(&self.cursor_position);
+        self.document.insert_text(&self.cursor_position, &text);
+        self.cursor_position = Position {
+            x: text.split('\n').nth(1).unwrap_or_default().graphemes(true).count(),
+            y: self.cursor_position.y + 1,
+        };
+        self.move_cursor(KeyCode::Null);
+    }
+    /// Takes one level of indentation off a line that is blank up to the
+    /// cursor when a closing bracket is typed there.
+    fn dedent(&mut self, c: char) {
+        let indent = match self.document.dedented(&self.cursor_position, c) {
+            Some(indent) => indent,
+            None => return,
+        };
+        let x = self.cursor_position.x;
+        self.cursor_position.x = indent.graphemes(true).count();
+        for _ in self.cursor_position.x..x {
+            self.document.delete(&self.cursor_position);
+        }
+    }
+    /// Jumps to the next tab stop of a snippet, expands the snippet whose
+    /// trigger is before the cursor, or inserts a tab.
+    fn tab(&mut self) {
+        if !self.tab_stops.is_empty() {
+            self.next_tab_stop();
+        } else if !self.expand_snippet() {
+            self.document.insert(&self.cursor_position, '\t');
+            self.move_cursor(KeyCode::Right);
+        }
+    }
+    /// Replaces the trigger before the cursor with its snippet, indented
+    /// like the current line.
+    fn expand_snippet(&mut self) -> bool {
+        match self
+            .document
+            .expand_snippet(&self.cursor_position, &self.uwuifier.snippets)
+        {
+            Some(stops) => {
+                self.tab_stops = stops;
+                self.next_tab_stop();
+                true
+            }
+            None => false,
+        }
+    }
+    /// Puts the cursor after the text of the next tab stop, selected so
+    /// typing replaces it.
+    fn next_tab_stop(&mut self) {
+        if self.tab_stops.is_empty() {
+            return;
+        }
+        let (start, len) = self.tab_stops.remove(0);
+        self.cursor_position = Position {
+            x: start.x + len,
+            y: start.y,
+        };
+        if len > 0 {
+            self.selection_anchor = Some(start.clone());
+            self.placeholder = Some((start, len));
+        }
+        self.move_cursor(KeyCode::Null);
+    }
+    /// Moves the tab stops still to come along with the text after `at`,
+    /// which was in a row `row_len` long of a document `rows` long before
+    /// the edit.
+    fn shift_tab_stops(&mut self, at: &Position, row_len: usize, rows: usize) {
+        let now = self.cursor_position.clone();
+        let now_len = self.document.row(now.y).map_or(0, Row::len);
+        let row_shift = self.document.len() as isize - rows as isize;
+        for (stop, _) in &mut self.tab_stops {
+            if stop.y == at.y && stop.x >= at.x {
+                // What followed the edit still ends the cursor's row.
+                *stop = Position {
+                    x: now_len.saturating_sub(row_len.saturating_sub(stop.x)),
+                    y: now.y,
+                };
+            } else if stop.y > at.y {
+                stop.y = (stop.y as isize + row_shift).max(0) as usize;
+            }
+        }
+    }
+    /// Offers words that finish the one before the cursor. The rest of the
+    /// chosen word is inserted as it is, without the uwu rules.
+    fn complete_word(&mut self) {
+        let Position { x, y } = self.cursor_position;
+        let prefix = x
+            .checked_sub(1)
+            .and_then(|before| self.document.row(y)?.word_at(before))
+            .filter(|(start, word)| start + word.graphemes(true).count() == x)
+            .map_or_else(String::new, |(_, word)| word.to_string());
+        let keywords = FileType::all_keywords();
+        let keywords: Vec<&str> = keywords.iter().map(String::as_str).collect();
+        let words = |editor: &Self, prefix: &str| {
+            let others: Vec<&Document> = editor.buffers.iter().map(|buffer| &buffer.document).collect();
+            completion::candidates(prefix, &editor.document, y, &others, &keywords)
+        };
+        let items = words(self, &prefix);
+        if items.is_empty() {
+            self.status_message = StatusMessage::from("Nothing to compwete (・・?)".to_string());
+            return;
+        }
+        self.picker = Some(Picker::new("compwetions", items));
+        let mut confirmed = false;
+        self.prompt(
+            &format!("Compwete {}", prefix),
+            PromptKind::Other,
+            |editor, key, query| match key.code {
+                KeyCode::Enter => confirmed = true,
+                KeyCode::Up => {
+                    if let Some(picker) = &mut editor.picker {
+                        picker.select_previous();
+                    }
+                }
+                KeyCode::Down => {
+                    if let Some(picker) = &mut editor.picker {
+                        picker.select_next();
+                    }
+                }
+                _ => {
+                    let items = words(editor, &format!("{}{}", prefix, query.text()));
+                    if let Some(picker) = &mut editor.picker {
+                        picker.set_items(items);
+                    }
+                }
+            },
+        )
+        .ok();
+        let word = self.picker.take().and_then(|picker| picker.selection().cloned());
+        if let (true, Some(word)) = (confirmed, word) {
+            let rest = &word[prefix.len()..];
+            self.document.insert_str(&self.cursor_position, rest);
+            self.cursor_position.x += rest.graphemes(true).count();
+        }
+    }
+    fn spelling(&mut self) {
+        if self.dictionary.is_none() {
+            self.status_message = StatusMessage::from("Speww checking is off (´・ω・`)".to_string());
+            return;
+        }
+        let Position { x, y } = self.cursor_position;
+        let word = self.document.row(y).and_then(|row| {
+            row.word_at(x)
+                .or_else(|| row.word_at(x.saturating_sub(1)))
+                .map(|(start, word)| (start, word.to_string()))
+        });
+        let (start, word) = if let Some(word) = word {
+            word
+        } else {
+            self.status_message = StatusMessage::from("Thewe is no wowd hewe (・・?)".to_string());
+            return;
+        };
+        let suggestions = self
+            .dictionary
+            .as_ref()
+            .map_or_else(Vec::new, |dictionary| dictionary.suggestions(&word, 5));
+        let listing: Vec<String> = suggestions
+            .iter()
+            .enumerate()
+            .map(|(index, suggestion)| format!("{}) {}", index + 1, suggestion))
+            .collect();
+        let answer = self
+            .prompt(
+                &format!("{} | + = add \"{}\": ", listing.join(" "), word),
+                PromptKind::Other,
+                |_, _, _| {},
+            )
+            .unwrap_or(None);
+        match answer.as_deref() {
+            Some("+") => {
+                if let Some(dictionary) = &mut self.dictionary {
+                    if dictionary.add(&word).is_err() {
+                        self.status_message = StatusMessage::from("Could not wwite the dictionawy! OWO".to_string());
+                    }
+                }
+                self.document.reset_spelling();
+            }
+            Some(answer) => {
+                if let Some(suggestion) = answer
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|number| suggestions.get(number.wrapping_sub(1)))
+                {
+                    let mut replacement = suggestion.clone();
+                    if word.starts_with(char::is_uppercase) {
+                        replacement = capitalize(&replacement);
+                    }
+                    let at = Position { x: start, y };
+                    self.document.replace(&at, word.graphemes(true).count(), &replacement);
+                    self.cursor_position = Position {
+                        x: start + replacement.graphemes(true).count(),
+                        y,
+                    };
+                }
+            }
+            None => (),
+        }
+    }
     fn process_keypress(&mut self) -> Result<(), std::io::Error> {
-        let event = Terminal::read(&mut self.terminal)?;
+        let event = if let Some(event) = Terminal::read(&mut self.terminal, TICK)? {
+            event
+        } else {
+            return Ok(());
+        };
 
         if let Event::Key(pressed_key) = event {
+            if self.browser.is_some() && self.browser_keypress(pressed_key) {
+                return Ok(());
+            }
+            if self.document.is_read_only() && is_edit(&pressed_key) {
+                if self.document.is_large() && !self.read_only {
+                    self.offer_full_load();
+                } else {
+                    self.status_message = StatusMessage::from(READ_ONLY_MESSAGE.to_string());
+                }
+                return Ok(());
+            }
+            self.document.commit();
+            let placeholder = self.placeholder.take().filter(|(start, len)| {
+                self.cursor_position == Position { x: start.x + len, y: start.y }
+            });
+            let at = placeholder
+                .as_ref()
+                .map_or_else(|| self.cursor_position.clone(), |(start, _)| start.clone());
+            let row_len = self.document.row(at.y).map_or(0, Row::len);
+            let rows = self.document.len();
+            if let (Some((start, len)), KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Delete) =
+                (placeholder, pressed_key.code)
+            {
+                if !pressed_key.modifiers.contains(KeyModifiers::CONTROL) {
+                    for _ in 0..len {
+                        self.document.delete(&start);
+                    }
+                    self.cursor_position = start;
+                    if !matches!(pressed_key.code, KeyCode::Char(_)) {
+                        self.shift_tab_stops(&at, row_len, rows);
+                        self.selection_anchor = None;
+                        return self.finish_keypress();
+                    }
+                }
+            }
             match (pressed_key.modifiers, pressed_key.code) {
                 (KeyModifiers::CONTROL, KeyCode::Char('q')) | (_, KeyCode::Esc) => {
-                    if self.quit_times > 0 && self.document.is_dirty() {
+                    if self.quit_times > 0 && self.has_unsaved_changes() {
                         self.status_message = StatusMessage::from(format!(
                             "OwO! Fiwe has unsawed changes!!! Pwess Esc {} mowe times to qwit1!!!",
                             self.quit_times
@@ -199,6 +1233,45 @@ impl Editor {
                 }
                 (KeyModifiers::CONTROL, KeyCode::Char('s')) => self.save(),
                 (KeyModifiers::CONTROL, KeyCode::Char('f')) => self.search(),
+                (KeyModifiers::CONTROL, KeyCode::Char('e')) => self.command_line(),
+                (KeyModifiers::CONTROL, KeyCode::Char('g')) => {
+                    let target = self
+                        .prompt("Go to (N, N:M, +N, -N, N%): ", PromptKind::Goto, |_, _, _| {})
+                        .unwrap_or(None);
+                    if let Some(target) = target {
+                        match goto::parse(&target) {
+                            Ok(target) => self.goto(&target),
+                            Err(error) => {
+                                self.status_message = StatusMessage::from(format!("EWWOR: {} (｡•́︿•̀｡)", error));
+                            }
+                        }
+                    }
+                }
+                (KeyModifiers::CONTROL, KeyCode::Char('w')) => self.spelling(),
+                (KeyModifiers::CONTROL, KeyCode::Char('k')) => self.pick_kaomoji(),
+                (KeyModifiers::CONTROL, KeyCode::Char('n'))
+                | (KeyModifiers::CONTROL, KeyCode::Char(' ')) => self.complete_word(),
+                (KeyModifiers::CONTROL, KeyCode::Char('o')) => {
+                    let dir = self.current_dir();
+                    if let Err(error) = self.browse(&dir) {
+                        self.status_message = StatusMessage::from(format!("EWWOR: {} (｡•́︿•̀｡)", error));
+                    }
+                }
+                (KeyModifiers::CONTROL, KeyCode::Char('z')) => {
+                    self.tab_stops.clear();
+                    if let Some(position) = self.document.undo() {
+                        self.cursor_position = position;
+                        self.move_cursor(KeyCode::Null);
+                    }
+                }
+                (KeyModifiers::CONTROL, KeyCode::PageDown) => self.switch_buffer(true),
+                (KeyModifiers::CONTROL, KeyCode::PageUp) => self.switch_buffer(false),
+                (KeyModifiers::CONTROL, KeyCode::Char('t')) => {
+                    self.show_statistics = !self.show_statistics;
+                    if self.dictionary.is_none() && self.statistics_dictionary.is_none() {
+                        self.statistics_dictionary = Some(Dictionary::load());
+                    }
+                }
                 (KeyModifiers::CONTROL, KeyCode::Char('l')) => {
                     self.document.insert(&self.cursor_position, 'l');
                     self.move_cursor(KeyCode::Right);
@@ -212,61 +1285,27 @@ impl Editor {
                     self.move_cursor(KeyCode::Right);
                 },
                 (_, KeyCode::Enter) => {
-                    self.document.insert(&self.cursor_position, '\n');
-                    self.move_cursor(KeyCode::Right);
-                },
-                (_, KeyCode::Char(mut c)) => {
-                    match c {
-                        'l' | 'r' => {
-                            c = 'w';
-                        },
-
-                        'L' | 'R' => {
-                            c = 'W';
-                        },
-                        '*' => {
-                            self.document.insert(&self.cursor_position, '*');
-                            self.document.insert(&self.cursor_position, ' ');
-                            self.document.insert(&self.cursor_position, 's');
-                            self.document.insert(&self.cursor_position, 'e');
-                            self.document.insert(&self.cursor_position, 'c');
-                            self.document.insert(&self.cursor_position, 'i');
-                            self.document.insert(&self.cursor_position, 't');
-                            self.document.insert(&self.cursor_position, 'o');
-                            self.document.insert(&self.cursor_position, 'n');
-                            self.document.insert(&self.cursor_position, '*');
-
-                            self.move_right(10);
-                            return Ok(());
-                        },
-
-                        'U' => {
-                            self.document.insert(&self.cursor_position, 'U');
-                            self.document.insert(&self.cursor_position, 'w');
-                            self.document.insert(&self.cursor_position, 'U');
-
-                            self.move_right(4);
-                            return Ok(());
-                        },
-
-                        'O' => {
-                            self.document.insert(&self.cursor_position, 'O');
-                            self.document.insert(&self.cursor_position, 'w');
-                            self.document.insert(&self.cursor_position, 'O');
-
-                            self.move_right(4);
-                            return Ok(());
-                        }
-                        _ => {}
+                    // A line break finishes a word just like a space does.
+                    let at = self.cursor_position.clone();
+                    if self.uwu && self.uwu_applies(' ') {
+                        self.substitute_word(&at);
                     }
-
-                    self.document.insert(&self.cursor_position, c);
-                    self.move_cursor(KeyCode::Right);
+                    self.insert_newline();
+                }
+                (_, KeyCode::Tab) => {
+                    self.tab();
+                    return self.finish_keypress();
                 }
+                (_, KeyCode::Char(c)) => self.type_char(c),
                 (_, KeyCode::Delete) => self.document.delete(&self.cursor_position),
-                (_, KeyCode::Backspace) => {
-                    if self.cursor_position.x > 0 || self.cursor_position.y > 0 {
-                        self.move_cursor(KeyCode::Left.into());
+                (_, KeyCode::Backspace)
+                    if self.cursor_position.x > 0 || self.cursor_position.y > 0 =>
+                {
+                    let in_pair = self.document.pair_edit(&self.cursor_position, None)
+                        == Some(PairEdit::DeletePair);
+                    self.move_cursor(KeyCode::Left);
+                    self.document.delete(&self.cursor_position);
+                    if in_pair {
                         self.document.delete(&self.cursor_position);
                     }
                 },
@@ -277,14 +1316,22 @@ impl Editor {
                 | (_, KeyCode::PageUp)
                 | (_, KeyCode::PageDown)
                 | (_, KeyCode::End)
-                | (_, KeyCode::Home) => self.move_cursor(pressed_key.code),
+                | (_, KeyCode::Home) => {
+                    if !pressed_key.modifiers.contains(KeyModifiers::SHIFT) {
+                        self.selection_anchor = None;
+                    } else if self.selection_anchor.is_none() {
+                        self.selection_anchor = Some(self.cursor_position.clone());
+                    }
+                    self.move_cursor(pressed_key.code);
+                    return self.finish_keypress();
+                }
                 _ => (),
             }
-            self.scroll();
-            if self.quit_times < QUIT_TIMES {
-                self.quit_times = QUIT_TIMES;
-                self.status_message = StatusMessage::from(String::new());
+            if is_edit(&pressed_key) {
+                self.shift_tab_stops(&at, row_len, rows);
             }
+            self.selection_anchor = None;
+            return self.finish_keypress();
         }
 
         else if let Event::Resize(width, height) = event {
@@ -300,11 +1347,145 @@ impl Editor {
 
         Ok(())
     }
+    /// Turns the document back into plain English, highlighting the words
+    /// the dictionary could not settle.
+    fn deuwuify(&mut self) -> Result<(), String> {
+        if self.document.is_read_only() {
+            return Err(READ_ONLY_MESSAGE.to_string());
+        }
+        let loaded;
+        let dictionary = match &self.dictionary {
+            Some(dictionary) => dictionary,
+            None => {
+                loaded = Dictionary::load();
+                &loaded
+            }
+        };
+        let mut ambiguous = 0;
+        for y in 0..self.document.len() {
+            let row = if let Some(row) = self.document.row(y) {
+                row
+            } else {
+                break;
+            };
+            let line = String::from_utf8_lossy(row.as_bytes()).to_string();
+            let len = row.len();
+            let (plain, marked) = uwu::deuwuify_line(&line, dictionary);
+            if plain != line {
+                self.document.replace(&Position { x: 0, y }, len, &plain);
+            }
+            ambiguous += marked.len();
+            self.document.mark(y, marked);
+        }
+        self.move_cursor(KeyCode::Null);
+        self.status_message = StatusMessage::from(match ambiguous {
+            0 => "Aww pwain now (◕‿◕✿)".to_string(),
+            1 => "1 wowd needs a wook, it's highwighted".to_string(),
+            _ => format!("{} wowds need a wook, they'we highwighted", ambiguous),
+        });
+        Ok(())
+    }
+    /// Swaps the word ending at `at` for its entry in the substitution
+    /// table, keeping the cursor after the same text.
+    fn substitute_word(&mut self, at: &Position) {
+        let substitutions = &self.uwuifier.substitutions;
+        self.cursor_position = self.document.substitute_word(at, &self.cursor_position, substitutions);
+    }
+    /// Whether `c` typed at the cursor goes through the uwu rules, going by
+    /// the uwu scope of the file type and what `c` would be highlighted as.
+    fn uwu_applies(&mut self, c: char) -> bool {
+        let scope = self
+            .uwu_scopes
+            .get(&self.document.file_type())
+            .copied()
+            .unwrap_or_else(|| self.document.uwu_scope());
+        match scope {
+            UwuScope::Prose => scope.covers(self.document.highlighting_at(&self.cursor_position, c)),
+            _ => scope == UwuScope::Everywhere,
+        }
+    }
+    fn has_unsaved_changes(&self) -> bool {
+        self.document.is_dirty() || self.buffers.iter().any(|buffer| buffer.document.is_dirty())
+    }
+    /// Shows the next (or previous) open file, keeping the one shown now
+    /// and its cursor for later.
+    fn switch_buffer(&mut self, forward: bool) {
+        let next = if self.buffers.is_empty() {
+            None
+        } else if forward {
+            Some(self.buffers.remove(0))
+        } else {
+            self.buffers.pop()
+        };
+        let next = if let Some(next) = next {
+            next
+        } else {
+            self.status_message = StatusMessage::from("Thewe is onwy one buffew (・ω・)".to_string());
+            return;
+        };
+        self.save_swap();
+        self.tab_stops.clear();
+        let current = Buffer {
+            document: std::mem::replace(&mut self.document, next.document),
+            cursor_position: std::mem::replace(&mut self.cursor_position, next.cursor_position),
+            offset: std::mem::replace(&mut self.offset, next.offset),
+        };
+        let count = self.buffers.len() + 2;
+        if forward {
+            self.buffers.push(current);
+            self.buffer_index = (self.buffer_index + 1) % count;
+        } else {
+            self.buffers.insert(0, current);
+            self.buffer_index = (self.buffer_index + count - 1) % count;
+        }
+        self.selection_anchor = None;
+        self.git_branch = statusbar::git_branch(self.document.file_name.as_deref());
+        self.swapped_revision = self.document.revision();
+        self.disk_change_ignored = false;
+        self.status_message = StatusMessage::from(format!(
+            "Buffew {}/{}: {}",
+            self.buffer_index + 1,
+            count,
+            self.document.file_name.as_deref().unwrap_or("[uwunamed]")
+        ));
+        self.offer_recovery();
+    }
+    /// Asks whether a large, read-only file should be read fully to edit it.
+    fn offer_full_load(&mut self) {
+        let answer = self
+            .prompt(
+                "This fiwe is big and wead-onwy. Woad aww of it to edit? (y/n): ",
+                PromptKind::Other,
+                |_, _, _| {},
+            )
+            .unwrap_or(None);
+        if answer.as_deref() != Some("y") {
+            return;
+        }
+        self.status_message = StatusMessage::from("Woading... (っ˘ω˘ς )".to_string());
+        self.refresh_screen().ok();
+        match self.document.load_fully() {
+            Ok(()) => {
+                self.status_message = StatusMessage::from("Aww woaded, edit away! (ง •̀_•́)ง".to_string());
+            }
+            Err(error) => {
+                self.status_message = StatusMessage::from(format!("Could not woad: {} (｡•́︿•̀｡)", error));
+            }
+        }
+    }
+    fn finish_keypress(&mut self) -> Result<(), std::io::Error> {
+        self.scroll();
+        if self.quit_times < QUIT_TIMES {
+            self.quit_times = QUIT_TIMES;
+            self.status_message = StatusMessage::from(String::new());
+        }
+        Ok(())
+    }
     fn scroll(&mut self) {
         let Position { x, y } = self.cursor_position;
         let width = self.terminal.size().width as usize;
         let height = self.terminal.size().height as usize;
-        let mut offset = &mut self.offset;
+        let offset = &mut self.offset;
         if y < offset.y {
             offset.y = y;
         } else if y >= offset.y.saturating_add(height) {
@@ -312,8 +1493,10 @@ impl Editor {
         }
         if x < offset.x {
             offset.x = x;
-        } else if x >= offset.x.saturating_add(width) {
-            offset.x = x.saturating_sub(width).saturating_add(1);
+        } else if let Some(row) = self.document.row(y) {
+            while offset.x < x && row.columns(offset.x, x, self.tab_width) >= width {
+                offset.x += 1;
+            }
         }
     }
     fn move_cursor(&mut self, key: KeyCode) {
@@ -327,11 +1510,7 @@ impl Editor {
         };
         match key {
             KeyCode::Up => y = y.saturating_sub(1),
-            KeyCode::Down => {
-                if y < height {
-                    y = y.saturating_add(1);
-                }
-            }
+            KeyCode::Down if y < height => y = y.saturating_add(1),
             KeyCode::Left => {
                 if x > 0 {
                     x -= 1;
@@ -382,17 +1561,11 @@ impl Editor {
         self.cursor_position = Position { x, y }
     }
 
-    fn move_right(&mut self, i: u8) {
-        for _ in 1..i {
-            self.move_cursor(KeyCode::Right);
-        }
-    }
-
     fn draw_centered(&self, r: &Row) {
         let mut width = self.terminal.size().width as usize;
         let start = self.offset.x;
         let end = self.offset.x.saturating_add(width);
-        let mut row = r.render(start, end);
+        let mut row = r.render(start, end, &self.theme, self.tab_width);
 
         let padding = width.saturating_sub(WELCOME_WIDTH) / 2;
         let spaces = " ".repeat(padding.saturating_sub(1));
@@ -415,16 +1588,27 @@ impl Editor {
         let width = self.terminal.size().width as usize;
         let start = self.offset.x;
         let end = self.offset.x.saturating_add(width);
-        let row = row.render(start, end);
+        let row = row.render(start, end, &self.theme, self.tab_width);
         println!("{}\r", row)
     }
     fn draw_rows(&self) {
         let height = self.terminal.size().height;
+        let flashed_row = self
+            .flashed_row
+            .filter(|(_, time)| time.elapsed() < FLASH_DURATION)
+            .map(|(y, _)| y);
 
         for terminal_row in 0..height {
             Terminal::clear_current_line();
-            if let Some(row) = self.document.row(self.offset.y.saturating_add(terminal_row as usize)) {
-                self.draw_row(row);
+            let y = self.offset.y.saturating_add(terminal_row as usize);
+            if let Some(row) = self.document.row(y) {
+                if flashed_row == Some(y) {
+                    Terminal::set_attribute(Attribute::Reverse);
+                    self.draw_row(row);
+                    Terminal::set_attribute(Attribute::NoReverse);
+                } else {
+                    self.draw_row(row);
+                }
             }
 
             else if self.document.is_empty() {
@@ -438,90 +1622,235 @@ impl Editor {
             }
         }
     }
+    fn draw_browser(&self, browser: &FileBrowser) {
+        let width = self.terminal.size().width as usize;
+        let height = self.terminal.size().height as usize;
+        let first = browser.first_visible(height);
+        for terminal_row in 0..height {
+            Terminal::clear_current_line();
+            let index = first + terminal_row;
+            let entry = if let Some(entry) = browser.entries.get(index) {
+                entry
+            } else {
+                println!("\r");
+                continue;
+            };
+            let (hl_type, suffix) = match entry.kind {
+                EntryKind::Directory => (highlighting::Type::PrimaryKeywords, "/"),
+                EntryKind::Executable => (highlighting::Type::String, "*"),
+                EntryKind::Symlink => (highlighting::Type::SecondaryKeywords, "@"),
+                EntryKind::File => (highlighting::Type::None, ""),
+            };
+            let text = format!(" {}{}", entry.name, suffix);
+            Terminal::set_foreground_color(self.theme.color(hl_type));
+            if index == browser.selected {
+                Terminal::set_attribute(Attribute::Reverse);
+                print!("{}", pad(&text, width));
+                Terminal::set_attribute(Attribute::NoReverse);
+                println!("\r");
+            } else {
+                println!("{}\r", statusbar::truncate(&text, width));
+            }
+            Terminal::set_foreground_color(Color::Reset);
+        }
+    }
     fn draw_status_bar(&self) {
-        let mut status;
         let width = self.terminal.size().width as usize;
-        let modified_indicator = if self.document.is_dirty() {
-            " (modified)"
-        } else {
-            ""
+        if let Some(browser) = &self.browser {
+            let left = format!(
+                " {} - {} entwies{}",
+                browser.dir.display(),
+                browser.entries.len(),
+                if browser.show_hidden { " [hidden]" } else { "" }
+            );
+            let right = "n new | r wename | d dewete | . hidden ";
+            let status = if left.width() + right.width() < width {
+                format!("{}{}", pad(&left, width - right.width()), right)
+            } else {
+                pad(&left, width)
+            };
+            Terminal::set_colors(self.theme.status_colors());
+            println!("{}\r", status);
+            Terminal::reset_colors();
+            return;
+        }
+        let selection = self.selection_anchor.as_ref().map_or(0, |anchor| {
+            self.document.count_between(anchor, &self.cursor_position)
+        });
+        let info = StatusInfo {
+            document: &self.document,
+            cursor_position: &self.cursor_position,
+            selection,
+            mood: statusbar::mood(self.document.is_dirty(), self.quit_times < QUIT_TIMES),
+            branch: self.git_branch.as_deref(),
+            uwu: if self.uwu {
+                self.uwuifier.intensity.name()
+            } else {
+                "off"
+            },
         };
+        let status = self.status_template.render(&info, width);
 
-        let mut file_name = "[uwunamed]".to_string();
-        if let Some(name) = &self.document.file_name {
-            file_name = name.clone();
-            file_name.truncate(20);
-        }
-        status = format!(
-            "{} - {} lines{}",
-            file_name,
-            self.document.len(),
-            modified_indicator
-        );
+        Terminal::set_colors(self.theme.status_colors());
 
-        let line_indicator = format!(
-            "{} | {}/{}",
-            self.document.file_type(),
-            self.cursor_position.y.saturating_add(1),
-            self.document.len()
-        );
-        let len = status.len() + line_indicator.len();
-        status.push_str(&" ".repeat(width.saturating_sub(len)));
-        status = format!("{}{}", status, line_indicator);
-        status.truncate(width);
+        println!("{}\r", status);
+        Terminal::reset_colors();
+    }
+    fn draw_statistics(&self, transformed_words: usize) {
+        let width = self.terminal.size().width as usize;
+        let height = self.terminal.size().height as usize;
+        if width < STATISTICS_WIDTH {
+            return;
+        }
+        let stats = self.document.stats();
+        let line = |label: &str, value: String| format!(" {:<12}{:>9} ", label, value);
+        let lines = [
+            format!(" {:<21} ", "~ statistics ~"),
+            line("words", stats.words.to_string()),
+            line("graphemes", stats.graphemes.to_string()),
+            line("sentences", stats.sentences.to_string()),
+            line("paragraphs", stats.paragraphs.to_string()),
+            line("weading", format!("{} min", stats.reading_time())),
+            line("this session", format!("{:+}", self.document.session_words())),
+            line("uwu density", format!("{}%", stats.uwu_density(transformed_words))),
+            line("UwU/OwO", stats.uwus.to_string()),
+            line("kaomoji", stats.kaomoji.to_string()),
+        ];
 
-        Terminal::set_colors(Colors::new(STATUS_BG_COLOR, STATUS_FG_COLOR));
+        Terminal::set_colors(self.theme.status_colors());
+        for (y, text) in lines.iter().take(height).enumerate() {
+            Terminal::cursor_position(&Position {
+                x: width - STATISTICS_WIDTH,
+                y,
+            });
+            print!("{}", text);
+        }
+        Terminal::reset_colors();
+    }
+    fn draw_picker(&self, picker: &Picker) {
+        let width = (self.terminal.size().width as usize).min(PICKER_WIDTH);
+        let height = (self.terminal.size().height as usize).min(PICKER_HEIGHT + 1);
+        let visible = height.saturating_sub(1);
+        let top = self.terminal.size().height as usize - height;
+        let first = picker.selected.saturating_sub(visible.saturating_sub(1));
 
-        println!("{}\r", status);
+        Terminal::set_colors(self.theme.status_colors());
+        Terminal::cursor_position(&Position { x: 0, y: top });
+        print!("{}", pad(&format!(" ~ {} ~", picker.title), width));
+        for (index, item) in picker.items.iter().enumerate().skip(first).take(visible) {
+            let marker = if index == picker.selected { '>' } else { ' ' };
+            Terminal::cursor_position(&Position {
+                x: 0,
+                y: top + 1 + index - first,
+            });
+            print!("{}", pad(&format!(" {} {}", marker, item), width));
+        }
         Terminal::reset_colors();
     }
     fn draw_message_bar(&self) {
         Terminal::clear_current_line();
         let message = &self.status_message;
         if Instant::now() - message.time < Duration::new(5, 0) {
-            let mut text = message.text.clone();
-            text.truncate(self.terminal.size().width as usize);
+            let text = statusbar::truncate(&message.text, self.terminal.size().width as usize);
             print!("{}", text);
         }
     }
-    fn prompt<C>(&mut self, prompt: &str, mut callback: C) -> Result<Option<String>, std::io::Error>
+    fn prompt<C>(
+        &mut self,
+        prompt: &str,
+        kind: PromptKind,
+        mut callback: C,
+    ) -> Result<Option<String>, std::io::Error>
     where
-        C: FnMut(&mut Self, KeyEvent, &String),
+        C: FnMut(&mut Self, KeyEvent, &mut LineEditor),
     {
-        let mut result = String::new();
+        let mut line = LineEditor::default();
         loop {
-            self.status_message = StatusMessage::from(format!("{}{}", prompt, result));
+            self.status_message = StatusMessage::from(format!("{}{}", prompt, line.text()));
             self.refresh_screen()?;
-            let event = read().unwrap();
+            let width = self.terminal.size().width as usize;
+            Terminal::cursor_position(&Position {
+                x: (prompt.width() + line.cursor_column()).min(width.saturating_sub(1)),
+                y: self.terminal.size().height as usize + 1,
+            });
+            Terminal::flush()?;
+            let event = read()?;
 
             if let Event::Key(key) = event {
-                match key.code {
-                    KeyCode::Backspace => result.truncate(result.len().saturating_sub(1)),
-                    KeyCode::Enter => break,
-                    KeyCode::Char(c) => {
-                        if !c.is_control() {
-                            result.push(c);
+                let history = self.histories.entry(kind).or_default();
+                match (key.modifiers, key.code) {
+                    (KeyModifiers::CONTROL, KeyCode::Char('w'))
+                    | (KeyModifiers::ALT, KeyCode::Backspace) => line.delete_word(),
+                    (_, KeyCode::Backspace) => line.backspace(),
+                    (_, KeyCode::Delete) => line.delete(),
+                    (_, KeyCode::Left) => line.left(),
+                    (_, KeyCode::Right) => line.right(),
+                    (_, KeyCode::Home) => line.home(),
+                    (_, KeyCode::End) => line.end(),
+                    (_, KeyCode::Up) if kind != PromptKind::Other => {
+                        if let Some(entry) = history.previous(line.text()) {
+                            line.set_text(entry);
                         }
                     }
-                    KeyCode::Esc => {
-                        result.truncate(0);
-                        break;
+                    (_, KeyCode::Down) if kind != PromptKind::Other => {
+                        if let Some(entry) = history.next() {
+                            line.set_text(entry);
+                        }
+                    }
+                    (_, KeyCode::Tab) if kind == PromptKind::SaveAs => {
+                        if let Some(completed) = prompt::complete_path(line.text()) {
+                            line.set_text(&completed);
+                        }
                     }
+                    (KeyModifiers::CONTROL, _) => (),
+                    (_, KeyCode::Char(c)) if !c.is_control() => line.insert(c),
+                    (_, KeyCode::Esc) => line.set_text(""),
                     _ => (),
                 }
-                callback(self, key, &result);
+                callback(self, key, &mut line);
+                if key.code == KeyCode::Enter || key.code == KeyCode::Esc {
+                    break;
+                }
             }
-
         }
         self.status_message = StatusMessage::from(String::new());
-        if result.is_empty() {
+        if line.text().is_empty() {
             return Ok(None);
         }
-        Ok(Some(result))
+        self.histories.entry(kind).or_default().push(line.text());
+        Ok(Some(line.text().to_string()))
     }
 }
 
-fn die(e: std::io::Error) {
-    Terminal::clear_screen();
-    panic!("{}", e);
+/// Truncates or pads `text` to exactly `width` terminal columns.
+fn pad(text: &str, width: usize) -> String {
+    let text = statusbar::truncate(text, width);
+    let padding = width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+/// Whether `key` changes the document.
+fn is_edit(key: &KeyEvent) -> bool {
+    match (key.modifiers, key.code) {
+        (KeyModifiers::CONTROL, KeyCode::Char(c)) => "lr*zwkn ".contains(c),
+        (_, KeyCode::Char(_))
+        | (_, KeyCode::Enter)
+        | (_, KeyCode::Tab)
+        | (_, KeyCode::Delete)
+        | (_, KeyCode::Backspace) => true,
+        _ => false,
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+fn die(error: &std::io::Error) -> ! {
+    Terminal::restore();
+    eprintln!("\r\nkyun had to stop: {} (╥﹏╥)", error);
+    std::process::exit(1);
 }
@@ -1,6 +1,20 @@
+use crate::highlighting;
+
 pub struct FileType {
     name: String,
     hl_opts: HighlightingOptions,
+    uwu_scope: UwuScope,
+    /// What typing the first of each pair also inserts after the cursor.
+    pairs: Vec<(char, char)>,
+}
+
+/// Where typed text goes through the uwu rules.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum UwuScope {
+    Everywhere,
+    /// Only inside strings and comments, so code keeps compiling.
+    Prose,
+    Nowhere,
 }
 
 #[derive(Default)]
@@ -20,6 +34,9 @@ impl Default for FileType {
         Self {
             name: String::from("No filetype"),
             hl_opts: HighlightingOptions::default(),
+            uwu_scope: UwuScope::Everywhere,
+            // Apostrophes are too common in prose to pair.
+            pairs: vec![('(', ')'), ('[', ']'), ('{', '}'), ('"', '"')],
         }
     }
 }
@@ -31,127 +48,209 @@ impl FileType {
     pub fn highlighting_options(&self) -> &HighlightingOptions {
         &self.hl_opts
     }
+    pub fn uwu_scope(&self) -> UwuScope {
+        self.uwu_scope
+    }
+    pub fn pairs(&self) -> &[(char, char)] {
+        &self.pairs
+    }
+    /// The primary and secondary keywords.
+    pub fn keywords(&self) -> impl Iterator<Item = &str> {
+        self.hl_opts
+            .primary_keywords
+            .iter()
+            .chain(&self.hl_opts.secondary_keywords)
+            .map(String::as_str)
+            .filter(|keyword| !keyword.is_empty())
+    }
+    /// The keywords of every file type, Rust and UwU++, whatever file they
+    /// are completed in.
+    pub fn all_keywords() -> Vec<String> {
+        let mut keywords: Vec<String> = Vec::new();
+        for file_type in [Self::rust(), Self::uwupp()].iter() {
+            for keyword in file_type.keywords() {
+                if !keywords.iter().any(|known| known == keyword) {
+                    keywords.push(keyword.to_string());
+                }
+            }
+        }
+        keywords
+    }
+    /// File types with keywords are programming languages rather than prose.
+    pub fn is_code(&self) -> bool {
+        !self.hl_opts.primary_keywords.is_empty()
+    }
     pub fn from(file_name: &str) -> Self {
         if file_name.ends_with(".rs") {
-            return Self {
-                name: String::from("Rust"),
-                hl_opts: HighlightingOptions {
-                    numbers: true,
-                    strings: true,
-                    asteriscs: false,
-                    characters: true,
-                    comments: true,
-                    multiline_comments: true,
-                    primary_keywords: vec![
-                        "as".to_string(),
-                        "break".to_string(),
-                        "const".to_string(),
-                        "continue".to_string(),
-                        "crate".to_string(),
-                        "else".to_string(),
-                        "enum".to_string(),
-                        "extern".to_string(),
-                        "false".to_string(),
-                        "fn".to_string(),
-                        "for".to_string(),
-                        "if".to_string(),
-                        "impl".to_string(),
-                        "in".to_string(),
-                        "let".to_string(),
-                        "loop".to_string(),
-                        "match".to_string(),
-                        "mod".to_string(),
-                        "move".to_string(),
-                        "mut".to_string(),
-                        "pub".to_string(),
-                        "ref".to_string(),
-                        "return".to_string(),
-                        "self".to_string(),
-                        "Self".to_string(),
-                        "static".to_string(),
-                        "struct".to_string(),
-                        "super".to_string(),
-                        "trait".to_string(),
-                        "true".to_string(),
-                        "type".to_string(),
-                        "unsafe".to_string(),
-                        "use".to_string(),
-                        "where".to_string(),
-                        "while".to_string(),
-                        "dyn".to_string(),
-                        "abstract".to_string(),
-                        "become".to_string(),
-                        "box".to_string(),
-                        "do".to_string(),
-                        "final".to_string(),
-                        "macro".to_string(),
-                        "override".to_string(),
-                        "priv".to_string(),
-                        "typeof".to_string(),
-                        "unsized".to_string(),
-                        "virtual".to_string(),
-                        "yield".to_string(),
-                        "async".to_string(),
-                        "await".to_string(),
-                        "try".to_string(),
-                    ],
-                    secondary_keywords: vec![
-                        "bool".to_string(),
-                        "char".to_string(),
-                        "i8".to_string(),
-                        "i16".to_string(),
-                        "i32".to_string(),
-                        "i64".to_string(),
-                        "isize".to_string(),
-                        "u8".to_string(),
-                        "u16".to_string(),
-                        "u32".to_string(),
-                        "u64".to_string(),
-                        "usize".to_string(),
-                        "f32".to_string(),
-                        "f64".to_string(),
-                    ],
-                },
-            };
+            return Self::rust();
+        } else if file_name.ends_with(".uwu") {
+            return Self::uwupp();
         }
+        Self::default()
+    }
+    /// Looks a file type up by the name users type, e.g. `:filetype rust`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "rust" | "rs" => Some(Self::rust()),
+            "uwu" | "uwu++" | "uwupp" => Some(Self::uwupp()),
+            "none" | "text" | "plain" => Some(Self::default()),
+            _ => None,
+        }
+    }
+    fn rust() -> Self {
+        Self {
+            name: String::from("Rust"),
+            uwu_scope: UwuScope::Prose,
+            // `'` starts lifetimes as often as it starts characters.
+            pairs: vec![('(', ')'), ('[', ']'), ('{', '}'), ('"', '"')],
+            hl_opts: HighlightingOptions {
+                numbers: true,
+                strings: true,
+                asteriscs: false,
+                characters: true,
+                comments: true,
+                multiline_comments: true,
+                primary_keywords: vec![
+                    "as".to_string(),
+                    "break".to_string(),
+                    "const".to_string(),
+                    "continue".to_string(),
+                    "crate".to_string(),
+                    "else".to_string(),
+                    "enum".to_string(),
+                    "extern".to_string(),
+                    "false".to_string(),
+                    "fn".to_string(),
+                    "for".to_string(),
+                    "if".to_string(),
+                    "impl".to_string(),
+                    "in".to_string(),
+                    "let".to_string(),
+                    "loop".to_string(),
+                    "match".to_string(),
+                    "mod".to_string(),
+                    "move".to_string(),
+                    "mut".to_string(),
+                    "pub".to_string(),
+                    "ref".to_string(),
+                    "return".to_string(),
+                    "self".to_string(),
+                    "Self".to_string(),
+                    "static".to_string(),
+                    "struct".to_string(),
+                    "super".to_string(),
+                    "trait".to_string(),
+                    "true".to_string(),
+                    "type".to_string(),
+                    "unsafe".to_string(),
+                    "use".to_string(),
+                    "where".to_string(),
+                    "while".to_string(),
+                    "dyn".to_string(),
+                    "abstract".to_string(),
+                    "become".to_string(),
+                    "box".to_string(),
+                    "do".to_string(),
+                    "final".to_string(),
+                    "macro".to_string(),
+                    "override".to_string(),
+                    "priv".to_string(),
+                    "typeof".to_string(),
+                    "unsized".to_string(),
+                    "virtual".to_string(),
+                    "yield".to_string(),
+                    "async".to_string(),
+                    "await".to_string(),
+                    "try".to_string(),
+                ],
+                secondary_keywords: vec![
+                    "bool".to_string(),
+                    "char".to_string(),
+                    "i8".to_string(),
+                    "i16".to_string(),
+                    "i32".to_string(),
+                    "i64".to_string(),
+                    "isize".to_string(),
+                    "u8".to_string(),
+                    "u16".to_string(),
+                    "u32".to_string(),
+                    "u64".to_string(),
+                    "usize".to_string(),
+                    "f32".to_string(),
+                    "f64".to_string(),
+                ],
+            },
+        }
+    }
+    fn uwupp() -> Self {
+        Self {
+            name: String::from("UwU++"),
+            // UwU++ is uwu all the way down, keywords included.
+            uwu_scope: UwuScope::Everywhere,
+            pairs: vec![('(', ')'), ('[', ']'), ('{', '}'), ('"', '"'), ('\'', '\'')],
+            hl_opts: HighlightingOptions {
+                numbers: true,
+                strings: true,
+                asteriscs: true,
+                characters: true,
+                comments: false,
+                multiline_comments: false,
+                primary_keywords: vec![
+                    "UwU".to_string(),
+                    "OwO".to_string(),
+                    "stawp".to_string(),
+                    "nuzzles".to_string(),
+                    "nyaa".to_string(),
+                    "wetuwn".to_string(),
+                    "UwU?".to_string(),
+                    "".to_string(),
+                    "for".to_string(),
+                    "if".to_string(),
 
-        else if file_name.ends_with(".uwu") {
-            return Self {
-                name: String::from("UwU++"),
-                hl_opts: HighlightingOptions {
-                    numbers: true,
-                    strings: true,
-                    asteriscs: true,
-                    characters: true,
-                    comments: false,
-                    multiline_comments: false,
-                    primary_keywords: vec![
-                        "UwU".to_string(),
-                        "OwO".to_string(),
-                        "stawp".to_string(),
-                        "nuzzles".to_string(),
-                        "nyaa".to_string(),
-                        "wetuwn".to_string(),
-                        "UwU?".to_string(),
-                        "".to_string(),
-                        "for".to_string(),
-                        "if".to_string(),
+                ],
+                secondary_keywords: vec![
+                    "iws".to_string(),
+                    "gweatew".to_string(),
+                    "pwus".to_string(),
+                    "eqwall twoo".to_string(),
+                    "minwus".to_string(),
+                    "diwide".to_string(),
+                    "wess twan".to_string(),
+                    "twimes".to_string(),
+                ],
+            },
+        }
+    }
+}
 
-                    ],
-                    secondary_keywords: vec![
-                        "iws".to_string(),
-                        "gweatew".to_string(),
-                        "pwus".to_string(),
-                        "eqwall twoo".to_string(),
-                        "minwus".to_string(),
-                        "diwide".to_string(),
-                        "wess twan".to_string(),
-                        "twimes".to_string(),
-                    ],
-                },
-            };
+impl UwuScope {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "everywhere" | "all" => Some(Self::Everywhere),
+            "prose" | "comments" | "strings" => Some(Self::Prose),
+            "nowhere" | "none" => Some(Self::Nowhere),
+            _ => None,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Everywhere => "everywhere",
+            Self::Prose => "prose",
+            Self::Nowhere => "nowhere",
+        }
+    }
+    /// Whether text highlighted as `hl_type` is uwuified.
+    pub fn covers(self, hl_type: highlighting::Type) -> bool {
+        match self {
+            Self::Everywhere => true,
+            Self::Prose => matches!(
+                hl_type,
+                highlighting::Type::String
+                    | highlighting::Type::Comment
+                    | highlighting::Type::MultilineComment
+            ),
+            Self::Nowhere => false,
         }
-        Self::default()
     }
 }
 
@@ -181,3 +280,36 @@ impl HighlightingOptions {
         self.multiline_comments
     }
 }
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_uwu_scope() {
+        assert_eq!(FileType::from("main.rs").uwu_scope(), UwuScope::Prose);
+        assert_eq!(FileType::from("notes.txt").uwu_scope(), UwuScope::Everywhere);
+        assert_eq!(UwuScope::from_name("Comments"), Some(UwuScope::Prose));
+        assert_eq!(UwuScope::from_name("sometimes"), None);
+        assert!(UwuScope::Prose.covers(highlighting::Type::MultilineComment));
+        assert!(!UwuScope::Prose.covers(highlighting::Type::PrimaryKeywords));
+        assert!(!UwuScope::Nowhere.covers(highlighting::Type::String));
+    }
+
+    #[test]
+    fn test_pairs() {
+        let quote = ('\'', '\'');
+        assert!(FileType::from("notes.uwu").pairs().contains(&quote));
+        assert!(!FileType::from("main.rs").pairs().contains(&quote));
+        assert!(FileType::default().pairs().contains(&('(', ')')));
+    }
+
+    #[test]
+    fn test_all_keywords() {
+        let keywords = FileType::all_keywords();
+        assert!(keywords.contains(&"impl".to_string()));
+        assert!(keywords.contains(&"nyaa".to_string()));
+        assert!(!keywords.contains(&String::new()));
+        assert_eq!(FileType::default().keywords().count(), 0);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::Position;
+
+/// Where the go-to prompt should take the cursor. Lines and columns are
+/// 1-based, as in compiler messages.
+#[derive(PartialEq, Debug)]
+pub enum Target {
+    Line { line: usize, column: Option<usize> },
+    Relative(isize),
+    Percent(usize),
+}
+
+/// Parses `N`, `N:M`, `file:N:M`, `+N`, `-N` and `N%`.
+pub fn parse(text: &str) -> Result<Target, String> {
+    let text = text.trim();
+    let number = |part: &str| {
+        part.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("{} is not a numbew", part.trim()))
+    };
+    if let Some(percent) = text.strip_suffix('%') {
+        return Ok(Target::Percent(number(percent)?.min(100)));
+    }
+    if let Some(lines) = text.strip_prefix('+') {
+        return Ok(Target::Relative(number(lines)? as isize));
+    }
+    if let Some(lines) = text.strip_prefix('-') {
+        return Ok(Target::Relative(-(number(lines)? as isize)));
+    }
+    let parts: Vec<&str> = text.rsplitn(3, ':').collect();
+    match parts.as_slice() {
+        [line] => Ok(Target::Line {
+            line: number(line)?,
+            column: None,
+        }),
+        // `file:line` and `line:column` look alike; a number decides.
+        [last, first] if first.trim().parse::<usize>().is_err() => Ok(Target::Line {
+            line: number(last)?,
+            column: None,
+        }),
+        [column, line] | [column, line, _] => Ok(Target::Line {
+            line: number(line)?,
+            column: Some(number(column)?),
+        }),
+        _ => Err(format!("{} is not a wine", text)),
+    }
+}
+
+impl Target {
+    /// Returns the 0-based row and, if one was given, column to jump to in a
+    /// document of `len` rows.
+    pub fn resolve(&self, current: &Position, len: usize) -> (usize, Option<usize>) {
+        let last = len.saturating_sub(1);
+        match self {
+            Target::Line { line, column } => (
+                line.saturating_sub(1).min(last),
+                column.map(|column| column.saturating_sub(1)),
+            ),
+            Target::Relative(lines) => {
+                let y = if *lines < 0 {
+                    current.y.saturating_sub(lines.unsigned_abs())
+                } else {
+                    current.y.saturating_add(*lines as usize)
+                };
+                (y.min(last), None)
+            }
+            Target::Percent(percent) => ((last * percent + 50) / 100, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("12"), Ok(Target::Line { line: 12, column: None }));
+        assert_eq!(parse("12:5"), Ok(Target::Line { line: 12, column: Some(5) }));
+        assert_eq!(parse("src/main.rs:12:5"), Ok(Target::Line { line: 12, column: Some(5) }));
+        assert_eq!(parse("src/main.rs:12"), Ok(Target::Line { line: 12, column: None }));
+        assert_eq!(parse("+3"), Ok(Target::Relative(3)));
+        assert_eq!(parse("-3"), Ok(Target::Relative(-3)));
+        assert_eq!(parse("50%"), Ok(Target::Percent(50)));
+        assert!(parse("nyaa").is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let current = Position { x: 0, y: 10 };
+        assert_eq!(Target::Relative(-20).resolve(&current, 100), (0, None));
+        assert_eq!(Target::Relative(200).resolve(&current, 100), (99, None));
+        assert_eq!(Target::Percent(50).resolve(&current, 101), (50, None));
+        assert_eq!(
+            Target::Line { line: 3, column: Some(4) }.resolve(&current, 100),
+            (2, Some(3))
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::config;
+use crate::picker;
+use std::fs;
+use std::path::PathBuf;
+
+const USER_KAOMOJI: &str = "kaomoji";
+const RECENT_KAOMOJI: &str = "kaomoji_recent";
+const MAX_RECENT: usize = 20;
+
+pub struct Kaomoji {
+    pub face: String,
+    pub tags: String,
+}
+
+pub struct Catalogue {
+    entries: Vec<Kaomoji>,
+    recent: Vec<String>,
+    recent_path: Option<PathBuf>,
+}
+
+impl Catalogue {
+    /// The bundled catalogue plus `<config dir>/kaomoji`, which has the same
+    /// `face<TAB>tags` format.
+    pub fn load() -> Self {
+        let mut catalogue = Self::parse(include_str!("kaomoji.txt"));
+        let dir = config::dir();
+        if let Some(dir) = &dir {
+            if let Ok(user_entries) = fs::read_to_string(dir.join(USER_KAOMOJI)) {
+                catalogue.entries.extend(Self::parse(&user_entries).entries);
+            }
+        }
+        catalogue.recent_path = dir.map(|dir| dir.join(RECENT_KAOMOJI));
+        if let Some(path) = &catalogue.recent_path {
+            if let Ok(recent) = fs::read_to_string(path) {
+                catalogue.recent = recent.lines().map(str::to_string).collect();
+            }
+        }
+        catalogue
+    }
+    pub fn parse(contents: &str) -> Self {
+        let entries = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (face, tags) = line.split_once('\t').unwrap_or((line, ""));
+                Kaomoji {
+                    face: face.trim().to_string(),
+                    tags: tags.trim().to_string(),
+                }
+            })
+            .collect();
+        Self {
+            entries,
+            recent: Vec::new(),
+            recent_path: None,
+        }
+    }
+    /// Entries whose tags fuzzily match `query`, recently used ones first.
+    pub fn search(&self, query: &str) -> Vec<&Kaomoji> {
+        let mut matches: Vec<(usize, usize, &Kaomoji)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let score = picker::fuzzy_score(query, &entry.tags)?;
+                let recency = self
+                    .recent
+                    .iter()
+                    .position(|face| *face == entry.face)
+                    .unwrap_or(MAX_RECENT);
+                Some((recency, score, entry))
+            })
+            .collect();
+        matches.sort_by_key(|(recency, score, _)| (*recency, *score));
+        matches.into_iter().map(|(_, _, entry)| entry).collect()
+    }
+    pub fn mark_used(&mut self, face: &str) {
+        self.recent.retain(|recent| recent != face);
+        self.recent.insert(0, face.to_string());
+        self.recent.truncate(MAX_RECENT);
+        if let Some(path) = &self.recent_path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).ok();
+            }
+            fs::write(path, self.recent.join("\n")).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let mut catalogue = Catalogue::parse("# comment\n(^_^)\thappy\n(;_;)\tsad\n(^o^)\thappy joy\n");
+        let faces = |catalogue: &Catalogue, query| {
+            catalogue
+                .search(query)
+                .iter()
+                .map(|entry| entry.face.clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(faces(&catalogue, "hap"), vec!["(^_^)", "(^o^)"]);
+        catalogue.mark_used("(^o^)");
+        assert_eq!(faces(&catalogue, "hap"), vec!["(^o^)", "(^_^)"]);
+        assert_eq!(faces(&catalogue, "").len(), 3);
+    }
+}
@@ -0,0 +1,135 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Byte offsets of the line starts of a file, found by a background thread
+/// so the first screen can be shown right away.
+pub struct LineIndex {
+    offsets: Arc<Mutex<Vec<u64>>>,
+    done: Arc<AtomicBool>,
+}
+
+impl LineIndex {
+    pub fn build(path: &Path) -> Result<Self, io::Error> {
+        let mut file = File::open(path)?;
+        let offsets = Arc::new(Mutex::new(vec![0]));
+        let done = Arc::new(AtomicBool::new(false));
+        let (thread_offsets, thread_done) = (Arc::clone(&offsets), Arc::clone(&done));
+        thread::spawn(move || {
+            let mut buffer = vec![0; CHUNK_SIZE];
+            let mut position = 0_u64;
+            while let Ok(read) = file.read(&mut buffer) {
+                if read == 0 {
+                    break;
+                }
+                let found: Vec<u64> = buffer[..read]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, byte)| **byte == b'\n')
+                    .map(|(index, _)| position + index as u64 + 1)
+                    .collect();
+                position += read as u64;
+                if let Ok(mut offsets) = thread_offsets.lock() {
+                    offsets.extend(found);
+                }
+            }
+            // A trailing line break does not start another line.
+            if let Ok(mut offsets) = thread_offsets.lock() {
+                if offsets.len() > 1 && offsets.last() == Some(&position) {
+                    offsets.pop();
+                }
+            }
+            thread_done.store(true, Ordering::Release);
+        });
+        Ok(Self { offsets, done })
+    }
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Acquire)
+    }
+    /// The number of lines found so far. Until indexing is done, the last
+    /// line may still be growing and is not counted.
+    pub fn lines(&self) -> usize {
+        let known = self.offsets.lock().map_or(0, |offsets| offsets.len());
+        if self.is_done() {
+            known
+        } else {
+            known.saturating_sub(1)
+        }
+    }
+    fn offset(&self, line: usize) -> Option<u64> {
+        self.offsets.lock().ok()?.get(line).copied()
+    }
+}
+
+/// A file too big to read in one go, whose lines are read on demand.
+pub struct LargeFile {
+    path: PathBuf,
+    index: LineIndex,
+}
+
+impl LargeFile {
+    pub fn open(path: &Path) -> Result<Self, io::Error> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            index: LineIndex::build(path)?,
+        })
+    }
+    pub fn lines(&self) -> usize {
+        self.index.lines()
+    }
+    pub fn is_indexed(&self) -> bool {
+        self.index.is_done()
+    }
+    /// Reads up to `count` lines from line `start` on, without their line
+    /// breaks. Invalid UTF-8 is replaced rather than refused.
+    pub fn read_lines(&self, start: usize, count: usize) -> Result<Vec<String>, io::Error> {
+        let offset = if let Some(offset) = self.index.offset(start) {
+            offset
+        } else {
+            return Ok(Vec::new());
+        };
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut reader = BufReader::new(file);
+        let mut lines = Vec::new();
+        let mut buffer = Vec::new();
+        while lines.len() < count.min(self.lines().saturating_sub(start)) {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                break;
+            }
+            while buffer.last() == Some(&b'\n') || buffer.last() == Some(&b'\r') {
+                buffer.pop();
+            }
+            lines.push(String::from_utf8_lossy(&buffer).to_string());
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_read_lines() {
+        let path = std::env::temp_dir().join(format!("kyun-large-{}.txt", std::process::id()));
+        std::fs::write(&path, "zewo\r\none\ntwo\nthwee\n").unwrap();
+        let file = LargeFile::open(&path).unwrap();
+        while !file.is_indexed() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(file.lines(), 4);
+        assert_eq!(file.read_lines(1, 2).unwrap(), vec!["one", "two"]);
+        assert_eq!(file.read_lines(3, 10).unwrap(), vec!["thwee"]);
+        assert_eq!(file.read_lines(0, 1).unwrap(), vec!["zewo"]);
+        assert!(file.read_lines(4, 1).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,79 @@
+//! The text engine behind kyun: documents and their rows, highlighting,
+//! prose statistics, uwu-aware spell checking and the [`uwu`] rules every
+//! typed character goes through. The terminal editor is built on top of it
+//! and needs the `tui` feature, which is on by default.
+//!
+//! ```
+//! use kyun::{uwu, Document, Position};
+//!
+//! assert_eq!(uwu::uwuify("Hello world"), "Hewwo wowwd");
+//!
+//! let mut document = Document::from_string("hewwo".to_string()).unwrap();
+//! document.insert_str(&Position { x: 5, y: 0 }, " OwO");
+//! assert_eq!(document.row(0).unwrap().as_bytes(), b"hewwo OwO");
+//! ```
+
+pub mod config;
+mod document;
+mod filetype;
+pub mod highlighting;
+mod large;
+mod row;
+mod snippets;
+mod spell;
+mod stats;
+mod substitutions;
+pub mod uwu;
+
+#[cfg(feature = "tui")]
+mod browser;
+#[cfg(feature = "tui")]
+#[doc(hidden)]
+pub mod cli;
+#[cfg(feature = "tui")]
+mod command;
+#[cfg(feature = "tui")]
+mod completion;
+#[cfg(feature = "tui")]
+mod diff;
+#[cfg(feature = "tui")]
+mod editor;
+#[cfg(feature = "tui")]
+mod goto;
+#[cfg(feature = "tui")]
+mod kaomoji;
+#[cfg(feature = "tui")]
+mod picker;
+#[cfg(feature = "tui")]
+mod prompt;
+#[cfg(feature = "tui")]
+mod statusbar;
+#[cfg(feature = "tui")]
+mod swap;
+#[cfg(feature = "tui")]
+mod terminal;
+#[cfg(feature = "tui")]
+mod theme;
+
+pub use document::Document;
+pub use document::LineEnding;
+pub use document::PairEdit;
+pub use document::Position;
+pub use document::SearchDirection;
+pub use filetype::FileType;
+pub use filetype::HighlightingOptions;
+pub use filetype::UwuScope;
+pub use row::Row;
+pub use snippets::Snippets;
+pub use spell::Dictionary;
+pub use stats::Statistics;
+pub use substitutions::Substitutions;
+
+#[cfg(feature = "tui")]
+pub use editor::Editor;
+#[cfg(feature = "tui")]
+pub use terminal::Terminal;
+#[cfg(feature = "tui")]
+pub use theme::ColorDepth;
+#[cfg(feature = "tui")]
+pub use theme::Theme;
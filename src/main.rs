@@ -1,18 +1,117 @@
-mod document;
-mod editor;
-mod filetype;
-mod highlighting;
-mod row;
-mod terminal;
-pub use document::Document;
-use editor::Editor;
-pub use editor::Position;
-pub use editor::SearchDirection;
-pub use filetype::FileType;
-pub use filetype::HighlightingOptions;
-pub use row::Row;
-pub use terminal::Terminal;
+use kyun::cli::{self, Action};
+use kyun::config::Config;
+use kyun::uwu::{self, Uwuifier};
+use kyun::{Dictionary, Editor, Snippets, Substitutions, Terminal};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+use std::process;
+use unicode_segmentation::UnicodeSegmentation;
 
 fn main() {
-    Editor::default().run();
+    let args = match cli::parse(env::args().skip(1)) {
+        Ok(Action::Edit(args)) => args,
+        Ok(Action::Uwuify {
+            file,
+            options,
+            intensity,
+            config,
+        }) => {
+            // Set up like the editor's, so the output matches typing.
+            let intensity = intensity.unwrap_or(load_config(config.as_deref()).uwu_intensity);
+            let mut uwuifier = Uwuifier::new(intensity, uwu::Rng::seed());
+            uwuifier.substitutions = Substitutions::load();
+            uwuifier.snippets = Snippets::load();
+            if let Err(error) = uwuify(file.as_deref(), &options, &mut uwuifier) {
+                eprintln!("kyun: {} (╥﹏╥)", error);
+                process::exit(1);
+            }
+            return;
+        }
+        Ok(Action::Deuwuify { file }) => {
+            if let Err(error) = deuwuify(file.as_deref()) {
+                eprintln!("kyun: {} (╥﹏╥)", error);
+                process::exit(1);
+            }
+            return;
+        }
+        Ok(Action::Help) => {
+            print!("{}", cli::HELP);
+            return;
+        }
+        Ok(Action::Version) => {
+            println!("kyun {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(error) => {
+            eprintln!("kyun: {} (twy --help) (・・;)", error);
+            process::exit(2);
+        }
+    };
+    let config = load_config(args.config.as_deref());
+    if !io::stdout().is_terminal() {
+        eprintln!("kyun is a tewminaw editow, but its output is not a tewminaw (・・?)");
+        process::exit(1);
+    }
+    Terminal::install_panic_hook();
+    match Editor::new(&args, &config) {
+        Ok(mut editor) => editor.run(),
+        Err(error) => {
+            eprintln!("kyun could not stawt the tewminaw: {} (╥﹏╥)", error);
+            process::exit(1);
+        }
+    }
+}
+
+/// Reads the config file given with `--config`, or the usual one.
+fn load_config(path: Option<&Path>) -> Config {
+    match path {
+        Some(path) => Config::open(path).unwrap_or_else(|error| {
+            eprintln!("kyun: could not wead {}: {} (・・;)", path.display(), error);
+            process::exit(2);
+        }),
+        None => Config::load(),
+    }
+}
+
+/// Reads `file`, or stdin for `None`.
+fn read_input(file: Option<&str>) -> Result<String, String> {
+    Ok(match file {
+        Some(file) => fs::read_to_string(file).map_err(|error| format!("could not wead {}: {}", file, error))?,
+        None => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|error| format!("could not wead stdin: {}", error))?;
+            text
+        }
+    })
+}
+
+/// Writes `file`, or stdin, to stdout as if it had been typed into kyun.
+fn uwuify(file: Option<&str>, options: &uwu::Options, uwuifier: &mut Uwuifier) -> Result<(), String> {
+    let text = read_input(file)?;
+    io::stdout()
+        .write_all(uwu::uwuify_text(&text, options, uwuifier).as_bytes())
+        .map_err(|error| format!("could not wwite: {}", error))
+}
+
+/// Writes `file`, or stdin, to stdout in plain English and lists the words
+/// that could have been several on stderr.
+fn deuwuify(file: Option<&str>) -> Result<(), String> {
+    let text = read_input(file)?;
+    let dictionary = Dictionary::load();
+    let mut plain = Vec::new();
+    for (y, line) in text.split('\n').enumerate() {
+        let (line, ambiguous) = uwu::deuwuify_line(line, &dictionary);
+        for (start, end) in ambiguous {
+            let word: String = line.graphemes(true).skip(start).take(end - start).collect();
+            eprintln!("kyun: {}:{}: {} couwd be mowe than one wowd (・・?)", y + 1, start + 1, word);
+        }
+        plain.push(line);
+    }
+    io::stdout()
+        .write_all(plain.join("\n").as_bytes())
+        .map_err(|error| format!("could not wwite: {}", error))
 }
@@ -0,0 +1,59 @@
+/// The state of a list overlay the user picks an entry from.
+pub struct Picker {
+    pub title: String,
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+impl Picker {
+    pub fn new(title: &str, items: Vec<String>) -> Self {
+        Self {
+            title: title.to_string(),
+            items,
+            selected: 0,
+        }
+    }
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        self.selected = 0;
+    }
+    pub fn select_next(&mut self) {
+        if self.selected.saturating_add(1) < self.items.len() {
+            self.selected += 1;
+        }
+    }
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+    pub fn selection(&self) -> Option<&String> {
+        self.items.get(self.selected)
+    }
+}
+
+/// Scores how well `query` matches `text` as a case-insensitive subsequence.
+/// Lower is better; `None` means no match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    for (index, c) in query.to_lowercase().chars().enumerate() {
+        let found = text[position..].iter().position(|t| *t == c)?;
+        // Leading junk counts less than gaps between the query's characters.
+        score += if index == 0 { found.min(1) } else { found * 2 };
+        position += found + 1;
+    }
+    Some(score + text.len().saturating_sub(position) / 8)
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("hpy", "happy"), Some(4));
+        assert_eq!(fuzzy_score("happy", "happy smile"), Some(0));
+        assert_eq!(fuzzy_score("sad", "happy"), None);
+        assert!(fuzzy_score("shy", "shy blush") < fuzzy_score("shy", "sleepy happy"));
+    }
+}
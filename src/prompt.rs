@@ -0,0 +1,220 @@
+use crate::command;
+use std::fs;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Every kind of prompt keeps a history of its own.
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PromptKind {
+    Search,
+    SaveAs,
+    Command,
+    Goto,
+    /// Prompts like the kaomoji picker, which use Up/Down themselves.
+    Other,
+}
+
+/// The text typed into a prompt, with a cursor counted in graphemes.
+#[derive(Default)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize,
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.len();
+    }
+    fn len(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+    fn byte_index(&self, at: usize) -> usize {
+        self.text
+            .grapheme_indices(true)
+            .nth(at)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+    /// The terminal columns before the cursor.
+    pub fn cursor_column(&self) -> usize {
+        self.text[..self.byte_index(self.cursor)].width()
+    }
+    pub fn insert(&mut self, c: char) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert(index, c);
+        // A combining character or joiner can merge graphemes, so the cursor
+        // goes after whichever grapheme now holds `c`.
+        let end = index + c.len_utf8();
+        self.cursor = self
+            .text
+            .grapheme_indices(true)
+            .take_while(|(start, _)| *start < end)
+            .count();
+    }
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.delete();
+        }
+    }
+    pub fn delete(&mut self) {
+        let start = self.byte_index(self.cursor);
+        let end = self.byte_index(self.cursor + 1);
+        self.text.replace_range(start..end, "");
+    }
+    /// Deletes from the start of the word before the cursor up to the cursor.
+    pub fn delete_word(&mut self) {
+        let end = self.byte_index(self.cursor);
+        let before = &self.text[..end];
+        let trimmed = before.trim_end();
+        let start = trimmed
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace() || *c == '/')
+            .map_or(0, |(index, c)| index + c.len_utf8());
+        self.text.replace_range(start..end, "");
+        self.cursor = self.text[..start].graphemes(true).count();
+    }
+    pub fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.len());
+    }
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+    pub fn end(&mut self) {
+        self.cursor = self.len();
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    entries: Vec<String>,
+    position: Option<usize>,
+    draft: String,
+}
+
+impl History {
+    pub fn push(&mut self, entry: &str) {
+        self.position = None;
+        if entry.is_empty() {
+            return;
+        }
+        self.entries.retain(|existing| existing != entry);
+        self.entries.push(entry.to_string());
+    }
+    /// Steps back in time. `current` is kept so that stepping forward past
+    /// the newest entry brings it back.
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            Some(0) => return None,
+            Some(position) => position - 1,
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+        };
+        self.position = Some(position);
+        self.entries.get(position).map(String::as_str)
+    }
+    pub fn next(&mut self) -> Option<&str> {
+        let position = self.position? + 1;
+        if position >= self.entries.len() {
+            self.position = None;
+            return Some(&self.draft);
+        }
+        self.position = Some(position);
+        self.entries.get(position).map(String::as_str)
+    }
+}
+
+/// Extends the path at the end of `text` as far as the file system allows,
+/// appending a separator after a single directory.
+pub fn complete_path(text: &str) -> Option<String> {
+    let split = text.rfind(['/', '\\']).map_or(0, |index| index + 1);
+    let (dir, prefix) = text.split_at(split);
+    let entries = fs::read_dir(if dir.is_empty() { "." } else { dir }).ok()?;
+    let mut names: Vec<(String, bool)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            Some((name, is_dir))
+        })
+        .filter(|(name, _)| name.starts_with(prefix))
+        .filter(|(name, _)| prefix.starts_with('.') || !name.starts_with('.'))
+        .collect();
+    names.sort();
+    let candidates: Vec<&str> = names.iter().map(|(name, _)| name.as_str()).collect();
+    let mut completed = command::common_prefix(&candidates);
+    if let [(_, true)] = names.as_slice() {
+        completed.push('/');
+    }
+    if completed.len() <= prefix.len() {
+        return None;
+    }
+    Some(format!("{}{}", dir, completed))
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_line_editor() {
+        let mut line = LineEditor::default();
+        line.set_text("hewwo wowwd");
+        line.backspace();
+        assert_eq!(line.text(), "hewwo woww");
+        line.delete_word();
+        assert_eq!(line.text(), "hewwo ");
+        line.home();
+        line.insert('ö');
+        line.right();
+        line.insert('\u{301}');
+        assert_eq!(line.text(), "öh\u{301}ewwo ");
+        assert_eq!(line.cursor_column(), 2);
+        line.backspace();
+        assert_eq!(line.text(), "öewwo ");
+        line.end();
+        line.left();
+        line.delete();
+        assert_eq!(line.text(), "öewwo");
+    }
+
+    #[test]
+    fn test_multibyte_edits() {
+        let mut line = LineEditor::default();
+        line.set_text("a\u{3000}b");
+        line.delete_word();
+        assert_eq!(line.text(), "a\u{3000}");
+        line.set_text("a\u{a0}b");
+        line.delete_word();
+        assert_eq!(line.text(), "a\u{a0}");
+        line.set_text("👨👩");
+        line.home();
+        line.right();
+        line.insert('\u{200d}');
+        assert_eq!(line.text(), "👨\u{200d}👩");
+        assert_eq!(line.cursor, 1);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = History::default();
+        history.push("first");
+        history.push("second");
+        assert_eq!(history.previous("draft"), Some("second"));
+        assert_eq!(history.previous("second"), Some("first"));
+        assert_eq!(history.previous("first"), None);
+        assert_eq!(history.next(), Some("second"));
+        assert_eq!(history.next(), Some("draft"));
+        assert_eq!(history.next(), None);
+    }
+}
@@ -1,19 +1,31 @@
 use crate::highlighting;
+use crate::Dictionary;
 use crate::HighlightingOptions;
 use crate::SearchDirection;
-use std::cmp;
-use crossterm::{
-    style::SetForegroundColor,
-    style::Color
-};
+use crate::Statistics;
+#[cfg(feature = "tui")]
+use crate::Theme;
+#[cfg(feature = "tui")]
+use crossterm::style::{Attribute, Color, SetAttribute, SetForegroundColor};
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Default)]
 pub struct Row {
     string: String,
     highlighting: Vec<highlighting::Type>,
     pub is_highlighted: bool,
+    /// Whether a multiline comment is still open at the end of the row, as
+    /// of the last highlighting.
+    ends_in_comment: bool,
     len: usize,
+    stats: Statistics,
+    /// Words typing changed, once counted, until the row changes.
+    transformed_words: Option<usize>,
+    misspelled: Vec<(usize, usize)>,
+    is_spell_checked: bool,
+    /// Grapheme ranges left for the user to look over, until the row changes.
+    marked: Vec<(usize, usize)>,
 }
 
 impl From<&str> for Row {
@@ -22,23 +34,33 @@ impl From<&str> for Row {
             string: String::from(slice),
             highlighting: Vec::new(),
             is_highlighted: false,
+            ends_in_comment: false,
             len: slice.graphemes(true).count(),
+            stats: Statistics::of_line(slice),
+            transformed_words: None,
+            misspelled: Vec::new(),
+            is_spell_checked: false,
+            marked: Vec::new(),
         }
     }
 }
 
 impl Row {
-    pub fn render(&self, start: usize, end: usize) -> String {
-        let end = cmp::min(end, self.string.len());
-        let start = cmp::min(start, end);
+    /// Renders the graphemes from `start` on that fit into `end - start`
+    /// terminal columns, expanding tabs to `tab_width` spaces.
+    #[cfg(feature = "tui")]
+    pub fn render(&self, start: usize, end: usize, theme: &Theme, tab_width: usize) -> String {
+        let columns = end.saturating_sub(start);
+        let mut used = 0;
         let mut result = String::new();
         let mut current_highlighting = &highlighting::Type::None;
-        for (index, grapheme) in self.string[..]
-            .graphemes(true)
-            .enumerate()
-            .skip(start)
-            .take(end - start)
-        {
+        let mut underlined = false;
+        for (index, grapheme) in self.string[..].graphemes(true).enumerate().skip(start) {
+            let width = grapheme_width(grapheme, tab_width);
+            if used + width > columns {
+                break;
+            }
+            used += width;
             if let Some(c) = grapheme.chars().next() {
                 let highlighting_type = self
                     .highlighting
@@ -46,30 +68,78 @@ impl Row {
                     .unwrap_or(&highlighting::Type::None);
                 if highlighting_type != current_highlighting {
                     current_highlighting = highlighting_type;
-                    result.push_str(format!("{}", SetForegroundColor(highlighting_type.to_color())).as_str());
+                    result.push_str(format!("{}", SetForegroundColor(highlighting_type.to_color(theme))).as_str());
 
                 }
+                if self.is_misspelled(index) != underlined {
+                    underlined = !underlined;
+                    let attribute = if underlined {
+                        Attribute::Underlined
+                    } else {
+                        Attribute::NoUnderline
+                    };
+                    result.push_str(format!("{}", SetAttribute(attribute)).as_str());
+                }
                 if c == '\t' {
-                    result.push_str(" ");
+                    result.push_str(&" ".repeat(tab_width));
                 } else {
                     result.push(c);
                 }
             }
         }
+        if underlined {
+            result.push_str(format!("{}", SetAttribute(Attribute::NoUnderline)).as_str());
+        }
         result.push_str(format!("{}", SetForegroundColor(Color::Reset)).as_str());
         // result.push_str(&end_highlight[..]);
         result
     }
+    /// The terminal columns taken up by the graphemes in `start..end`.
+    pub fn columns(&self, start: usize, end: usize, tab_width: usize) -> usize {
+        self.string[..]
+            .graphemes(true)
+            .skip(start)
+            .take(end.saturating_sub(start))
+            .map(|grapheme| grapheme_width(grapheme, tab_width))
+            .sum()
+    }
     pub fn len(&self) -> usize {
         self.len
     }
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
+    pub fn word_count(&self) -> usize {
+        self.stats.words
+    }
+    pub fn stats(&self) -> Statistics {
+        self.stats
+    }
+    fn update_stats(&mut self) {
+        self.stats = Statistics::of_line(&self.string);
+        self.transformed_words = None;
+    }
+    /// See [`Statistics::transformed_words`]. Counted once per change, as
+    /// it takes a few dictionary lookups per word.
+    pub fn transformed_words(&mut self, dictionary: &Dictionary) -> usize {
+        let string = &self.string;
+        *self
+            .transformed_words
+            .get_or_insert_with(|| Statistics::transformed_words(string, dictionary))
+    }
+    /// The whitespace the row starts with.
+    pub fn indentation(&self) -> &str {
+        &self.string[..self.string.len() - self.string.trim_start().len()]
+    }
+    pub fn is_blank(&self) -> bool {
+        self.string.trim().is_empty()
+    }
     pub fn insert(&mut self, at: usize, c: char) {
+        self.marked.clear();
         if at >= self.len() {
             self.string.push(c);
             self.len += 1;
+            self.update_stats();
             return;
         }
         let mut result: String = String::new();
@@ -84,11 +154,24 @@ impl Row {
         }
         self.len = length;
         self.string = result;
+        self.update_stats();
+    }
+    pub fn insert_str(&mut self, at: usize, text: &str) {
+        self.marked.clear();
+        let byte_index = self
+            .string
+            .grapheme_indices(true)
+            .nth(at)
+            .map_or(self.string.len(), |(byte_index, _)| byte_index);
+        self.string.insert_str(byte_index, text);
+        self.len = self.string.graphemes(true).count();
+        self.update_stats();
     }
     pub fn delete(&mut self, at: usize) {
         if at >= self.len() {
             return;
         }
+        self.marked.clear();
         let mut result: String = String::new();
         let mut length = 0;
         for (index, grapheme) in self.string[..].graphemes(true).enumerate() {
@@ -99,10 +182,12 @@ impl Row {
         }
         self.len = length;
         self.string = result;
+        self.update_stats();
     }
     pub fn append(&mut self, new: &Self) {
         self.string = format!("{}{}", self.string, new.string);
         self.len += new.len;
+        self.update_stats();
     }
     pub fn split(&mut self, at: usize) -> Self {
         let mut row: String = String::new();
@@ -122,12 +207,83 @@ impl Row {
         self.string = row;
         self.len = length;
         self.is_highlighted = false;
+        self.update_stats();
         Self {
+            stats: Statistics::of_line(&splitted_row),
+            transformed_words: None,
             string: splitted_row,
             len: splitted_length,
             is_highlighted: false,
+            ends_in_comment: false,
             highlighting: Vec::new(),
+            misspelled: Vec::new(),
+            is_spell_checked: false,
+            marked: Vec::new(),
+        }
+    }
+    /// Returns the grapheme index where the word at `at` starts, and the word.
+    pub fn word_at(&self, at: usize) -> Option<(usize, &str)> {
+        let byte_index = self.string.grapheme_indices(true).nth(at)?.0;
+        let (start, word) = self
+            .string
+            .unicode_word_indices()
+            .find(|(start, word)| *start <= byte_index && byte_index < start + word.len())?;
+        Some((self.string[..start].graphemes(true).count(), word))
+    }
+    pub fn grapheme(&self, at: usize) -> Option<&str> {
+        self.string.graphemes(true).nth(at)
+    }
+    pub fn is_misspelled(&self, index: usize) -> bool {
+        self.misspelled
+            .iter()
+            .any(|(start, end)| *start <= index && index < *end)
+    }
+    /// Underlines the words the dictionary doesn't know. In code, only
+    /// strings and comments are prose.
+    pub fn spell_check(&mut self, dictionary: &Dictionary, is_code: bool) {
+        if self.is_spell_checked {
+            return;
         }
+        self.misspelled = Vec::new();
+        let grapheme_starts: Vec<usize> = self
+            .string
+            .grapheme_indices(true)
+            .map(|(byte_index, _)| byte_index)
+            .collect();
+        for (byte_index, word) in self.string.unicode_word_indices() {
+            let start = grapheme_starts
+                .binary_search(&byte_index)
+                .unwrap_or_else(|index| index);
+            let end = start + word.graphemes(true).count();
+            let is_prose = (start..end).all(|index| match self.highlighting.get(index) {
+                Some(highlighting::Type::String)
+                | Some(highlighting::Type::Comment)
+                | Some(highlighting::Type::MultilineComment)
+                | Some(highlighting::Type::Asteriscs) => true,
+                Some(highlighting::Type::None) | None => !is_code,
+                _ => false,
+            });
+            if is_prose && !dictionary.is_valid(word) {
+                self.misspelled.push((start, end));
+            }
+        }
+        self.is_spell_checked = true;
+    }
+    pub fn reset_spelling(&mut self) {
+        self.is_spell_checked = false;
+        self.misspelled = Vec::new();
+    }
+    /// Highlights the grapheme ranges in `marked` like search matches.
+    pub fn mark(&mut self, marked: Vec<(usize, usize)>) {
+        self.marked = marked;
+        self.is_highlighted = false;
+    }
+    /// The highlighting type of each grapheme, once `highlight` has run.
+    pub fn ends_in_comment(&self) -> bool {
+        self.ends_in_comment
+    }
+    pub fn highlighting(&self) -> &[highlighting::Type] {
+        &self.highlighting
     }
     pub fn as_bytes(&self) -> &[u8] {
         self.string.as_bytes()
@@ -234,7 +390,7 @@ impl Row {
                 }
             }
 
-            if self.highlight_str(index, &word, chars, hl_type) {
+            if self.highlight_str(index, word, chars, hl_type) {
                 return true;
             }
         }
@@ -432,17 +588,10 @@ impl Row {
     ) -> bool {
         let chars: Vec<char> = self.string.chars().collect();
         if self.is_highlighted && word.is_none() {
-            if let Some(hl_type) = self.highlighting.last() {
-                if *hl_type == highlighting::Type::MultilineComment
-                    && self.string.len() > 1
-                    && self.string[self.string.len() - 2..] == *"*/"
-                {
-                    return true;
-                }
-            }
-            return false;
+            return self.ends_in_comment;
         }
         self.highlighting = Vec::new();
+        self.is_spell_checked = false;
         let mut index = 0;
         let mut in_ml_comment = start_with_comment;
         if in_ml_comment {
@@ -457,15 +606,15 @@ impl Row {
             index = closing_index;
         }
         while let Some(c) = chars.get(index) {
-            if self.highlight_multiline_comment(&mut index, &opts, *c, &chars) {
+            if self.highlight_multiline_comment(&mut index, opts, *c, &chars) {
                 in_ml_comment = true;
                 continue;
             }
             in_ml_comment = false;
             if self.highlight_char(&mut index, opts, *c, &chars)
                 || self.highlight_comment(&mut index, opts, *c, &chars)
-                || self.highlight_primary_keywords(&mut index, &opts, &chars)
-                || self.highlight_secondary_keywords(&mut index, &opts, &chars)
+                || self.highlight_primary_keywords(&mut index, opts, &chars)
+                || self.highlight_secondary_keywords(&mut index, opts, &chars)
                 || self.highlight_string(&mut index, opts, *c, &chars)
                 || self.highlight_asteriscs(&mut index, opts, *c, &chars)
                 || self.highlight_number(&mut index, opts, *c, &chars)
@@ -476,11 +625,23 @@ impl Row {
             index += 1;
         }
         self.highlight_match(word);
-        if in_ml_comment && &self.string[self.string.len().saturating_sub(2)..] != "*/" {
-            return true;
+        for &(start, end) in &self.marked {
+            for hl_type in self.highlighting.iter_mut().take(end).skip(start) {
+                *hl_type = highlighting::Type::Match;
+            }
         }
+        self.ends_in_comment =
+            in_ml_comment && &self.string[self.string.len().saturating_sub(2)..] != "*/";
         self.is_highlighted = true;
-        false
+        self.ends_in_comment
+    }
+}
+
+fn grapheme_width(grapheme: &str, tab_width: usize) -> usize {
+    if grapheme == "\t" {
+        tab_width
+    } else {
+        grapheme.width()
     }
 }
 
@@ -530,4 +691,17 @@ mod test_super {
         assert_eq!(row.find("t", 2, SearchDirection::Forward), Some(4));
         assert_eq!(row.find("t", 5, SearchDirection::Forward), Some(5));
     }
+
+    #[test]
+    fn test_ends_in_comment() {
+        let file_type = crate::FileType::from("main.rs");
+        let opts = file_type.highlighting_options();
+        let mut row = Row::from("x /* hewwo");
+        assert!(row.highlight(opts, &None, false));
+        assert!(row.is_highlighted && row.ends_in_comment());
+        assert!(row.highlight(opts, &None, false));
+        let mut row = Row::from("stiww */ x");
+        assert!(!row.highlight(opts, &None, true));
+        assert!(!row.ends_in_comment());
+    }
 }
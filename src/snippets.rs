@@ -0,0 +1,238 @@
+use crate::config;
+use crate::uwu;
+use crate::FileType;
+use crate::Position;
+use std::collections::HashMap;
+use std::fs;
+use unicode_segmentation::UnicodeSegmentation;
+
+const USER_SNIPPETS: &str = "snippets";
+const EXTENSION: &str = "snippets";
+/// The table whose snippets work in every file type.
+const ALL: &str = "all";
+
+pub struct Snippet {
+    pub trigger: String,
+    /// `trigger` as typing it with the uwu rules leaves it.
+    typed: String,
+    body: String,
+    /// Expands as soon as its one-character trigger is typed, as one of
+    /// the uwu rules, rather than on Tab.
+    pub auto: bool,
+}
+
+/// A snippet ready to insert: its lines and its tab stops, each a position
+/// relative to the start of the snippet and the length of its text.
+#[derive(PartialEq, Debug)]
+pub struct Body {
+    pub lines: Vec<String>,
+    pub stops: Vec<(Position, usize)>,
+}
+
+/// Snippets by file type name.
+#[derive(Default)]
+pub struct Snippets {
+    snippets: HashMap<String, Vec<Snippet>>,
+}
+
+impl Snippets {
+    pub fn bundled() -> Self {
+        let mut snippets = Self::default();
+        snippets.add(ALL, include_str!("snippets/all.snippets"));
+        snippets.add("rust", include_str!("snippets/rust.snippets"));
+        snippets.add("uwu", include_str!("snippets/uwupp.snippets"));
+        snippets
+    }
+    /// The bundled snippets plus `<config dir>/snippets/<file type>.snippets`
+    /// and `all.snippets`, whose snippets win over bundled ones with the
+    /// same trigger.
+    pub fn load() -> Self {
+        let mut snippets = Self::bundled();
+        let dir = config::dir().map(|dir| dir.join(USER_SNIPPETS));
+        if let Some(Ok(entries)) = dir.map(fs::read_dir) {
+            for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+                if path.extension().is_none_or(|extension| extension != EXTENSION) {
+                    continue;
+                }
+                let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string());
+                if let (Some(name), Ok(contents)) = (name, fs::read_to_string(&path)) {
+                    snippets.add(&name, &contents);
+                }
+            }
+        }
+        snippets
+    }
+    /// Adds the snippets in `contents` to the file type called `name`.
+    pub fn add(&mut self, name: &str, contents: &str) {
+        let file_type = match FileType::from_name(name) {
+            Some(file_type) => file_type.name(),
+            None if name == ALL => ALL.to_string(),
+            None => return,
+        };
+        let snippets = self.snippets.entry(file_type).or_default();
+        for snippet in parse(contents).into_iter().rev() {
+            snippets.insert(0, snippet);
+        }
+    }
+    /// The Tab snippet for `trigger` in `file_type`, or in every file type.
+    /// The trigger may have been typed through the uwu rules, at any
+    /// intensity.
+    pub fn find(&self, file_type: &str, trigger: &str) -> Option<&Snippet> {
+        let plain = uwu::without_intensity(trigger);
+        [file_type, ALL]
+            .iter()
+            .filter_map(|name| self.snippets.get(*name))
+            .flatten()
+            .filter(|snippet| !snippet.auto)
+            .find(|snippet| snippet.trigger == trigger || snippet.typed == trigger || snippet.typed == plain)
+    }
+    /// What typing `c` expands to if it triggers an autosnippet: the first
+    /// line of its body and where the cursor goes in it.
+    pub fn expand(&self, c: char) -> Option<(String, usize)> {
+        let mut trigger = [0; 4];
+        let trigger = c.encode_utf8(&mut trigger);
+        let snippet = self
+            .snippets
+            .get(ALL)?
+            .iter()
+            .find(|snippet| snippet.auto && snippet.trigger == *trigger)?;
+        let Body { mut lines, stops } = snippet.body("");
+        let cursor = stops
+            .iter()
+            .find(|(at, _)| at.y == 0)
+            .map_or(0, |(at, _)| at.x);
+        Some((lines.swap_remove(0), cursor))
+    }
+}
+
+/// Reads `snippet <trigger>` (or `autosnippet <trigger>`) ... `endsnippet`
+/// blocks; lines outside of them are comments.
+pub fn parse(contents: &str) -> Vec<Snippet> {
+    let mut snippets = Vec::new();
+    let mut current: Option<Snippet> = None;
+    for line in contents.lines() {
+        match current.as_mut() {
+            None => {
+                let (trigger, auto) = match (line.strip_prefix("snippet "), line.strip_prefix("autosnippet ")) {
+                    (Some(trigger), _) => (trigger, false),
+                    (_, Some(trigger)) => (trigger, true),
+                    _ => continue,
+                };
+                let trigger = trigger.trim();
+                current = Some(Snippet {
+                    typed: uwu::typed(trigger),
+                    trigger: trigger.to_string(),
+                    body: String::new(),
+                    auto,
+                });
+            }
+            Some(_) if line.trim_end() == "endsnippet" => snippets.extend(current.take()),
+            Some(snippet) => {
+                if !snippet.body.is_empty() {
+                    snippet.body.push('\n');
+                }
+                snippet.body.push_str(line);
+            }
+        }
+    }
+    snippets
+}
+
+impl Snippet {
+    /// Fills the placeholders in with their text and puts `indent` in
+    /// front of every line but the first. The stops are in the order they
+    /// are jumped to: `$1`, `$2`, ... and then `$0`, or the end.
+    pub fn body(&self, indent: &str) -> Body {
+        let mut lines = vec![String::new()];
+        let mut stops: Vec<(usize, Position, usize)> = Vec::new();
+        let mut chars = self.body.chars().peekable();
+        while let Some(c) = chars.next() {
+            let y = lines.len() - 1;
+            let line = lines.last_mut().expect("there is always a line");
+            match c {
+                '\n' => lines.push(indent.to_string()),
+                '\\' if chars.peek() == Some(&'$') => line.push(chars.next().unwrap_or('$')),
+                '$' if chars.peek().is_some_and(|c| *c == '{' || c.is_ascii_digit()) => {
+                    let braced = chars.next_if_eq(&'{').is_some();
+                    let mut number = String::new();
+                    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                        number.push(digit);
+                    }
+                    let mut text = String::new();
+                    if braced {
+                        if chars.next_if_eq(&':').is_some() {
+                            while let Some(c) = chars.next_if(|c| *c != '}') {
+                                text.push(c);
+                            }
+                        }
+                        chars.next_if_eq(&'}');
+                    }
+                    let at = Position {
+                        x: line.graphemes(true).count(),
+                        y,
+                    };
+                    let number = number.parse().unwrap_or(0);
+                    if !stops.iter().any(|(existing, _, _)| *existing == number) {
+                        stops.push((number, at, text.graphemes(true).count()));
+                    }
+                    line.push_str(&text);
+                }
+                _ => line.push(c),
+            }
+        }
+        if !stops.iter().any(|(number, _, _)| *number == 0) {
+            let end = Position {
+                x: lines.last().map_or(0, |line| line.graphemes(true).count()),
+                y: lines.len() - 1,
+            };
+            stops.push((0, end, 0));
+        }
+        // $0 comes last.
+        stops.sort_by_key(|(number, _, _)| number.wrapping_sub(1));
+        Body {
+            lines,
+            stops: stops.into_iter().map(|(_, at, len)| (at, len)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_body() {
+        let snippets = parse("# fn\nsnippet fn\nfn ${1:name}($2) {\n    $0\n}\nendsnippet\n");
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].trigger, "fn");
+        let at = |x, y| Position { x, y };
+        assert_eq!(
+            snippets[0].body("  "),
+            Body {
+                lines: vec![
+                    "fn name() {".to_string(),
+                    "      ".to_string(),
+                    "  }".to_string()
+                ],
+                stops: vec![(at(3, 0), 4), (at(8, 0), 0), (at(6, 1), 0)],
+            }
+        );
+        let plain = parse("snippet cost\n\\$5 ${1:each}\nendsnippet").remove(0);
+        assert_eq!(
+            plain.body(""),
+            Body {
+                lines: vec!["$5 each".to_string()],
+                stops: vec![(at(3, 0), 4), (at(7, 0), 0)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let snippets = Snippets::bundled();
+        assert_eq!(snippets.expand('*'), Some(("*notices *".to_string(), 9)));
+        assert_eq!(snippets.expand('x'), None);
+        assert!(snippets.find("Rust", "*").is_none());
+        assert!(snippets.find("Rust", "fn").is_some());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::config;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+const SYSTEM_WORDS: &str = "/usr/share/dict/words";
+const USER_DICTIONARY: &str = "dictionary";
+const SUFFIXES: [&str; 6] = ["s", "es", "ed", "d", "ing", "ly"];
+/// Words with more `w`s than this are not worth 3^n guesses.
+const MAX_AMBIGUOUS_WS: usize = 6;
+
+pub struct Dictionary {
+    words: HashSet<String>,
+    user_path: Option<PathBuf>,
+}
+
+impl Dictionary {
+    /// Uses the system word list if there is one, the bundled one otherwise,
+    /// plus the per-user dictionary.
+    pub fn load() -> Self {
+        let system = fs::read_to_string(SYSTEM_WORDS)
+            .unwrap_or_else(|_| include_str!("words.txt").to_string());
+        let mut dictionary = Self::from_words(&system);
+        dictionary.user_path = config::dir().map(|dir| dir.join(USER_DICTIONARY));
+        if let Some(path) = &dictionary.user_path {
+            if let Ok(user_words) = fs::read_to_string(path) {
+                dictionary.extend(&user_words);
+            }
+        }
+        dictionary
+    }
+    pub fn from_words(words: &str) -> Self {
+        let mut dictionary = Self {
+            words: HashSet::new(),
+            user_path: None,
+        };
+        dictionary.extend(words);
+        dictionary
+    }
+    fn extend(&mut self, words: &str) {
+        self.words
+            .extend(words.split_whitespace().map(str::to_lowercase));
+    }
+    /// Adds a word to the per-user dictionary file.
+    pub fn add(&mut self, word: &str) -> Result<(), std::io::Error> {
+        let word = word.to_lowercase();
+        if let Some(path) = &self.user_path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", word)?;
+        }
+        self.words.insert(word);
+        Ok(())
+    }
+    /// Whether `word` is in the list as it is, without taking suffixes off.
+    pub fn contains_exactly(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+    pub fn contains(&self, word: &str) -> bool {
+        if self.contains_exactly(word) {
+            return true;
+        }
+        let word = word.to_lowercase();
+        SUFFIXES.iter().any(|suffix| {
+            word.strip_suffix(suffix)
+                .is_some_and(|stem| stem.len() > 1 && self.words.contains(stem))
+        })
+    }
+    /// Whether `word` is spelled right, either as written or as an uwu form
+    /// of a real word.
+    pub fn is_valid(&self, word: &str) -> bool {
+        if word.chars().count() < 2 || word.chars().any(|c| c.is_ascii_digit()) {
+            return true;
+        }
+        if word == "UwU" || word == "OwO" || self.contains(word) {
+            return true;
+        }
+        unuwu_candidates(word)
+            .iter()
+            .any(|candidate| self.contains(candidate))
+    }
+    /// Dictionary words one or two edits away from `word`, closest first.
+    pub fn suggestions(&self, word: &str, limit: usize) -> Vec<String> {
+        let word = word.to_lowercase();
+        let mut suggestions: Vec<(usize, &String)> = self
+            .words
+            .iter()
+            .filter(|candidate| {
+                (candidate.len() as isize - word.len() as isize).abs() <= 2
+            })
+            .map(|candidate| (edit_distance(&word, candidate), candidate))
+            .filter(|(distance, _)| *distance <= 2)
+            .collect();
+        suggestions.sort();
+        suggestions
+            .into_iter()
+            .take(limit)
+            .map(|(_, candidate)| candidate.clone())
+            .collect()
+    }
+}
+
+/// Every spelling `word` might have had before kyun turned its l's and r's
+/// into w's.
+pub fn unuwu_candidates(word: &str) -> Vec<String> {
+    let ws = word.chars().filter(|c| *c == 'w' || *c == 'W').count();
+    if ws == 0 || ws > MAX_AMBIGUOUS_WS {
+        return Vec::new();
+    }
+    let mut candidates = vec![String::new()];
+    for c in word.chars() {
+        let options: &[char] = match c {
+            'w' => &['w', 'l', 'r'],
+            'W' => &['W', 'L', 'R'],
+            _ => &[],
+        };
+        if options.is_empty() {
+            candidates.iter_mut().for_each(|candidate| candidate.push(c));
+            continue;
+        }
+        candidates = candidates
+            .iter()
+            .flat_map(|candidate| {
+                options.iter().map(move |option| {
+                    let mut next = candidate.clone();
+                    next.push(*option);
+                    next
+                })
+            })
+            .collect();
+    }
+    candidates
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_is_valid() {
+        let dictionary = Dictionary::from_words("hello world love");
+        assert!(dictionary.is_valid("hewwo"));
+        assert!(dictionary.is_valid("Wowld"));
+        assert!(dictionary.is_valid("loves"));
+        assert!(dictionary.is_valid("UwU"));
+        assert!(!dictionary.is_valid("hewwx"));
+        assert_eq!(dictionary.suggestions("wold", 1), vec!["world".to_string()]);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::uwu;
+use crate::Dictionary;
+use std::ops::{AddAssign, SubAssign};
+use unicode_segmentation::UnicodeSegmentation;
+
+const WORDS_PER_MINUTE: usize = 200;
+const FACE_CHARS: &str = "ω꒳´`◕‿▽∀ㅂ﹏°ロ＼／ヾﾉ๑｡・ᴗ≧≦╥ಥ";
+
+/// Prose figures for a piece of text. Rows keep their own and the document
+/// keeps the running total, so an edit only recounts the rows it touched.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct Statistics {
+    pub words: usize,
+    pub graphemes: usize,
+    pub sentences: usize,
+    pub paragraphs: usize,
+    pub uwus: usize,
+    pub kaomoji: usize,
+}
+
+impl Statistics {
+    /// Counts a single line. Paragraphs depend on the neighbouring lines and
+    /// are left to the document.
+    pub fn of_line(line: &str) -> Self {
+        let mut stats = Self {
+            graphemes: line.graphemes(true).count(),
+            ..Self::default()
+        };
+        for word in line.unicode_words() {
+            stats.words += 1;
+            if word == "UwU" || word == "OwO" {
+                stats.uwus += 1;
+            }
+        }
+        stats.kaomoji = line.split_whitespace().filter(|token| is_kaomoji(token)).count();
+
+        let mut previous_terminator = false;
+        for c in line.chars() {
+            let terminator = ['.', '!', '?', '。', '！', '？'].contains(&c);
+            if terminator && !previous_terminator {
+                stats.sentences += 1;
+            }
+            previous_terminator = terminator;
+        }
+        stats
+    }
+    /// Reading time in minutes, rounded up.
+    pub fn reading_time(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE)
+    }
+    /// The percentage of words that went through the uwu treatment, given
+    /// how many did (see [`Statistics::transformed_words`]).
+    pub fn uwu_density(&self, transformed_words: usize) -> usize {
+        if self.words == 0 {
+            return 0;
+        }
+        transformed_words * 100 / self.words
+    }
+    /// How many words of `line` typing changed, i.e. differ from the
+    /// spelling the dictionary gives back for them. A `w` alone doesn't
+    /// tell: "how" or "window" were typed as they are.
+    pub fn transformed_words(line: &str, dictionary: &Dictionary) -> usize {
+        line.unicode_words()
+            .filter(|word| {
+                *word == "UwU"
+                    || *word == "OwO"
+                    || uwu::original_spelling(word, dictionary).is_some_and(|spelling| spelling != *word)
+            })
+            .count()
+    }
+}
+
+impl AddAssign for Statistics {
+    fn add_assign(&mut self, other: Self) {
+        self.words += other.words;
+        self.graphemes += other.graphemes;
+        self.sentences += other.sentences;
+        self.paragraphs += other.paragraphs;
+        self.uwus += other.uwus;
+        self.kaomoji += other.kaomoji;
+    }
+}
+
+impl SubAssign for Statistics {
+    fn sub_assign(&mut self, other: Self) {
+        self.words = self.words.saturating_sub(other.words);
+        self.graphemes = self.graphemes.saturating_sub(other.graphemes);
+        self.sentences = self.sentences.saturating_sub(other.sentences);
+        self.paragraphs = self.paragraphs.saturating_sub(other.paragraphs);
+        self.uwus = self.uwus.saturating_sub(other.uwus);
+        self.kaomoji = self.kaomoji.saturating_sub(other.kaomoji);
+    }
+}
+
+fn is_kaomoji(token: &str) -> bool {
+    token.chars().any(|c| FACE_CHARS.contains(c))
+        || ["^^", "^_^", "^o^", ">_<", ">w<", "^w^", "T_T", "o_o", "0w0"].contains(&token)
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_of_line() {
+        let stats = Statistics::of_line("Hewwo fwiend! UwU (◕‿◕) How are you?");
+        assert_eq!(stats.words, 6);
+        assert_eq!(stats.sentences, 2);
+        assert_eq!(stats.uwus, 1);
+        assert_eq!(stats.kaomoji, 1);
+        let dictionary = Dictionary::from_words("hello friend how are you");
+        let transformed = Statistics::transformed_words("Hewwo fwiend! UwU (◕‿◕) How are you?", &dictionary);
+        assert_eq!(transformed, 3);
+        assert_eq!(stats.uwu_density(transformed), 50);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::Document;
+use crate::Position;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+pub const DEFAULT_LEFT: &str = "{name} - {lines} lines {modified}";
+pub const DEFAULT_RIGHT: &str = "uwu: {uwu} | {filetype} | {line}/{lines}";
+const NAME_WIDTH: usize = 20;
+
+#[derive(PartialEq, Debug)]
+enum Segment {
+    Text(String),
+    Name,
+    Lines,
+    Modified,
+    FileType,
+    Line,
+    Column,
+    Percent,
+    Encoding,
+    LineEnding,
+    Words,
+    Selection,
+    Mood,
+    Branch,
+    Uwu,
+}
+
+impl Segment {
+    fn from(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(Segment::Name),
+            "lines" => Some(Segment::Lines),
+            "modified" => Some(Segment::Modified),
+            "filetype" => Some(Segment::FileType),
+            "line" => Some(Segment::Line),
+            "column" => Some(Segment::Column),
+            "percent" => Some(Segment::Percent),
+            "encoding" => Some(Segment::Encoding),
+            "line_ending" => Some(Segment::LineEnding),
+            "words" => Some(Segment::Words),
+            "selection" => Some(Segment::Selection),
+            "mood" => Some(Segment::Mood),
+            "branch" => Some(Segment::Branch),
+            "uwu" => Some(Segment::Uwu),
+            _ => None,
+        }
+    }
+}
+
+/// Everything the segments may show, gathered by the editor once per redraw.
+pub struct StatusInfo<'a> {
+    pub document: &'a Document,
+    pub cursor_position: &'a Position,
+    pub selection: usize,
+    pub mood: &'a str,
+    pub branch: Option<&'a str>,
+    /// The uwu intensity, or "off".
+    pub uwu: &'a str,
+}
+
+pub struct StatusTemplate {
+    left: Vec<Segment>,
+    right: Vec<Segment>,
+}
+
+impl Default for StatusTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_LEFT, DEFAULT_RIGHT)
+    }
+}
+
+impl StatusTemplate {
+    pub fn parse(left: &str, right: &str) -> Self {
+        Self {
+            left: parse_section(left),
+            right: parse_section(right),
+        }
+    }
+    pub fn render(&self, info: &StatusInfo, width: usize) -> String {
+        let right = truncate(&render_section(&self.right, info), width);
+        let right_width = right.width();
+        let left = truncate(
+            &render_section(&self.left, info),
+            width.saturating_sub(right_width),
+        );
+        let padding = width.saturating_sub(left.width() + right_width);
+        format!("{}{}{}", left, " ".repeat(padding), right)
+    }
+}
+
+/// Splits a section such as `{name} - {lines} lines` into segments; braces
+/// that don't name a segment are kept as text.
+fn parse_section(template: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let segment = rest
+            .find('}')
+            .and_then(|close| Segment::from(&rest[1..close]).map(|segment| (segment, close)));
+        if let Some((segment, close)) = segment {
+            if !text.is_empty() {
+                segments.push(Segment::Text(text));
+                text = String::new();
+            }
+            segments.push(segment);
+            rest = &rest[close + 1..];
+        } else {
+            text.push('{');
+            rest = &rest[1..];
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    segments
+}
+
+fn render_section(segments: &[Segment], info: &StatusInfo) -> String {
+    let document = info.document;
+    let mut result = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => result.push_str(text),
+            Segment::Name => {
+                let name = document.file_name.as_deref().unwrap_or("[uwunamed]");
+                result.push_str(&truncate(name, NAME_WIDTH));
+            }
+            Segment::Lines => {
+                result.push_str(&document.len().to_string());
+                // Lines of a large file are still being counted.
+                if document.is_indexing() {
+                    result.push('+');
+                }
+            }
+            Segment::Modified => {
+                if document.is_dirty() {
+                    result.push_str("(modified)");
+                } else if document.is_read_only() {
+                    result.push_str("[RO]");
+                }
+            }
+            Segment::FileType => result.push_str(&document.file_type()),
+            Segment::Line => {
+                result.push_str(&info.cursor_position.y.saturating_add(1).to_string());
+            }
+            Segment::Column => {
+                result.push_str(&info.cursor_position.x.saturating_add(1).to_string());
+            }
+            Segment::Percent => {
+                let percent = if document.is_empty() {
+                    0
+                } else {
+                    info.cursor_position.y.saturating_add(1).min(document.len()) * 100
+                        / document.len()
+                };
+                result.push_str(&format!("{}%", percent));
+            }
+            Segment::Encoding => result.push_str("UTF-8"),
+            Segment::LineEnding => result.push_str(document.line_ending().name()),
+            Segment::Words => result.push_str(&format!("{} words", document.word_count())),
+            Segment::Selection => {
+                if info.selection > 0 {
+                    result.push_str(&format!("{} sel", info.selection));
+                }
+            }
+            Segment::Mood => result.push_str(info.mood),
+            Segment::Branch => {
+                if let Some(branch) = info.branch {
+                    result.push_str(branch);
+                }
+            }
+            Segment::Uwu => result.push_str(info.uwu),
+        }
+    }
+    result
+}
+
+/// Cuts `text` down to at most `width` terminal columns without splitting
+/// a grapheme.
+pub fn truncate(text: &str, width: usize) -> String {
+    let mut result = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        used += grapheme.width();
+        if used > width {
+            break;
+        }
+        result.push_str(grapheme);
+    }
+    result
+}
+
+pub fn mood(dirty: bool, quitting: bool) -> &'static str {
+    if quitting {
+        "(°ロ°)!!"
+    } else if dirty {
+        "(・`ω´・)"
+    } else {
+        "(◕‿◕)"
+    }
+}
+
+/// Finds the branch of the git repository containing `file_name`, or the
+/// working directory for unnamed buffers.
+pub fn git_branch(file_name: Option<&str>) -> Option<String> {
+    let start = match file_name {
+        Some(name) => fs::canonicalize(name)
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf)),
+        None => env::current_dir().ok(),
+    }?;
+    for dir in start.ancestors() {
+        let dot_git = dir.join(".git");
+        let git_dir = if dot_git.is_dir() {
+            dot_git
+        } else if let Ok(link) = fs::read_to_string(&dot_git) {
+            // Worktrees and submodules have a `.git` file pointing elsewhere.
+            let target = PathBuf::from(link.trim().strip_prefix("gitdir:")?.trim());
+            dir.join(target)
+        } else {
+            continue;
+        };
+        let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+        let head = head.trim();
+        return Some(match head.strip_prefix("ref: refs/heads/") {
+            Some(branch) => branch.to_string(),
+            None => head.chars().take(7).collect(),
+        });
+    }
+    None
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_parse_section() {
+        assert_eq!(
+            parse_section("{name} - {nope} {lines}"),
+            vec![
+                Segment::Name,
+                Segment::Text(" - {nope} ".to_string()),
+                Segment::Lines,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("hewwo", 3), "hew");
+        assert_eq!(truncate("キュン.txt", 5), "キュ");
+        assert_eq!(truncate("e\u{301}e\u{301}", 1), "e\u{301}");
+    }
+}
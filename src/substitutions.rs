@@ -0,0 +1,101 @@
+use crate::config;
+use crate::uwu;
+use std::fs;
+
+const USER_SUBSTITUTIONS: &str = "substitutions";
+
+struct Substitution {
+    word: String,
+    /// `word` as typing it would have left it.
+    typed: String,
+    replacement: String,
+}
+
+/// Whole words swapped for their uwu forms once they are finished.
+#[derive(Default)]
+pub struct Substitutions {
+    entries: Vec<Substitution>,
+}
+
+impl Substitutions {
+    /// The bundled table plus `<config dir>/substitutions`, whose
+    /// `word = replacement` lines win over the bundled ones.
+    pub fn load() -> Self {
+        let mut substitutions = Self::parse(include_str!("substitutions.txt"));
+        if let Some(dir) = config::dir() {
+            if let Ok(user_entries) = fs::read_to_string(dir.join(USER_SUBSTITUTIONS)) {
+                let mut entries = Self::parse(&user_entries).entries;
+                entries.append(&mut substitutions.entries);
+                substitutions.entries = entries;
+            }
+        }
+        substitutions
+    }
+    pub fn parse(contents: &str) -> Self {
+        let entries = config::entries(contents)
+            .filter(|(word, replacement)| !word.is_empty() && !replacement.is_empty())
+            .map(|(word, replacement)| {
+                let word = word.to_lowercase();
+                Substitution {
+                    typed: uwu::uwuify(&word).to_lowercase(),
+                    word,
+                    replacement: replacement.to_string(),
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+    /// What to put in place of `word`, typed with or without the uwu rules,
+    /// in the same case as `word`.
+    pub fn get(&self, word: &str) -> Option<String> {
+        let lowercase = word.to_lowercase();
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.word == lowercase || entry.typed == lowercase)?;
+        let replacement = match_case(word, &entry.replacement);
+        if replacement == word {
+            None
+        } else {
+            Some(replacement)
+        }
+    }
+}
+
+/// Characters that finish the word before them. Apostrophes and hyphens
+/// belong to words, and `*` starts an action rather than ending one.
+pub fn is_word_boundary(c: char) -> bool {
+    c.is_whitespace() || (c.is_ascii_punctuation() && !matches!(c, '\'' | '-' | '*' | '_'))
+}
+
+/// `replacement` in all caps if `original` is, capitalized if `original`
+/// is, and as it is otherwise.
+fn match_case(original: &str, replacement: &str) -> String {
+    let letters = original.chars().filter(|c| c.is_alphabetic()).count();
+    if letters > 1 && original.chars().all(|c| !c.is_lowercase()) {
+        return replacement.to_uppercase();
+    }
+    let mut chars = replacement.chars();
+    match (original.chars().next(), chars.next()) {
+        (Some(first), Some(c)) if first.is_uppercase() => {
+            c.to_uppercase().chain(chars).collect()
+        }
+        _ => replacement.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let substitutions = Substitutions::parse("love = wuv\nsmall = smol\nthe = da\n");
+        assert_eq!(substitutions.get("wove"), Some("wuv".to_string()));
+        assert_eq!(substitutions.get("Love"), Some("Wuv".to_string()));
+        assert_eq!(substitutions.get("SMAWW"), Some("SMOL".to_string()));
+        assert_eq!(substitutions.get("The"), Some("Da".to_string()));
+        assert_eq!(substitutions.get("da"), None);
+        assert_eq!(substitutions.get("lovely"), None);
+    }
+}
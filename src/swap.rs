@@ -0,0 +1,82 @@
+use crate::config;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+/// Where unsaved changes to `file_name` are kept: `.name.kyun.swp` next to
+/// the file, or `unnamed.kyun.swp` in the config directory for new buffers.
+pub fn path(file_name: Option<&str>) -> Option<PathBuf> {
+    match file_name {
+        Some(file_name) => {
+            let path = Path::new(file_name);
+            let name = path.file_name()?.to_string_lossy();
+            Some(path.with_file_name(format!(".{}.kyun.swp", name)))
+        }
+        None => config::dir().map(|dir| dir.join("unnamed.kyun.swp")),
+    }
+}
+
+/// Writes the swap file, readable only by its owner, since it sits next
+/// to a file that may be private.
+pub fn write(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to new files, not to one left by an older run.
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents)
+}
+
+pub fn remove(path: &Path) {
+    fs::remove_file(path).ok();
+}
+
+/// Returns the swap file for `file_name` if there is one that was written
+/// after the file itself was last saved.
+pub fn newer(file_name: Option<&str>) -> Option<PathBuf> {
+    let swap = path(file_name)?;
+    let swapped = fs::metadata(&swap).and_then(|metadata| metadata.modified()).ok()?;
+    let saved = file_name.and_then(|file_name| {
+        fs::metadata(file_name)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    });
+    match saved {
+        Some(saved) if saved > swapped => None,
+        _ => Some(swap),
+    }
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_path() {
+        assert_eq!(
+            path(Some("notes/diary.txt")),
+            Some(PathBuf::from("notes/.diary.txt.kyun.swp"))
+        );
+        assert_eq!(path(Some("diary.txt")), Some(PathBuf::from(".diary.txt.kyun.swp")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_is_private() {
+        let path = std::env::temp_dir().join(format!(".kyun-swap-{}.kyun.swp", std::process::id()));
+        fs::write(&path, "owd").unwrap();
+        write(&path, b"hewwo").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(fs::read(&path).unwrap(), b"hewwo");
+        remove(&path);
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
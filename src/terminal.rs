@@ -1,11 +1,14 @@
+use crate::ColorDepth;
 use crate::Position;
 use std::io::{stdout, Write};
+use std::panic;
+use std::time::Duration;
 
 use crossterm::{
-    style::{Color, SetForegroundColor, Colors, SetColors, ResetColor},
+    style::{Attribute, Color, SetAttribute, SetForegroundColor, Colors, SetColors, ResetColor},
     ExecutableCommand,
     QueueableCommand,
-    event::{read, Event},
+    event::{poll, read, Event},
     cursor,
     terminal,
 };
@@ -17,32 +20,75 @@ pub struct Size {
 pub struct Terminal {
     pub size: Size,
     color: usize,
+    color_depth: ColorDepth,
+}
+
+const ANSI16_CYCLE: [Color; 6] = [
+    Color::Yellow,
+    Color::Green,
+    Color::Cyan,
+    Color::Blue,
+    Color::Magenta,
+    Color::Red,
+];
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        Terminal::restore();
+    }
 }
 
 impl Terminal {
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Result<Self, std::io::Error> {
-        let size = terminal::size().unwrap();
-        terminal::enable_raw_mode().ok();
+        let size = terminal::size()?;
+        terminal::enable_raw_mode()?;
 
         Ok(Self {
             size: Size {
                 width: size.0,
                 height: size.1.saturating_sub(2),
             },
-            color: 190
+            color: 190,
+            color_depth: ColorDepth::detect(),
         })
     }
     pub fn size(&self) -> &Size {
         &self.size
     }
+    pub fn color_depth(&self) -> ColorDepth {
+        self.color_depth
+    }
+    pub fn set_color_depth(&mut self, color_depth: ColorDepth) {
+        self.color_depth = color_depth;
+    }
     pub fn quit() {
-        Terminal::reset_colors();
+        Terminal::restore();
         stdout().execute(terminal::Clear(terminal::ClearType::All)).ok();
-        crossterm::terminal::disable_raw_mode().ok();
 
         println!("\n\t            ^   ^    \n\tBye bye ヾ(｡>﹏<｡)ﾉ\r\n");
 
 
+    }
+    /// Puts the terminal back the way the shell expects it: cooked mode, a
+    /// visible cursor and default colors. Safe to call more than once.
+    pub fn restore() {
+        let mut stdout = stdout();
+        stdout.execute(ResetColor).ok();
+        stdout.execute(SetAttribute(Attribute::Reset)).ok();
+        stdout.execute(cursor::Show).ok();
+        stdout.execute(cursor::EnableBlinking).ok();
+        terminal::disable_raw_mode().ok();
+    }
+    /// Makes panics restore the terminal before their message is printed,
+    /// so it does not end up garbled in raw mode.
+    pub fn install_panic_hook() {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            Terminal::restore();
+            println!();
+            hook(info);
+        }));
     }
     pub fn clear_screen() {
         stdout().execute(terminal::Clear(terminal::ClearType::All)).ok();
@@ -55,7 +101,12 @@ impl Terminal {
             self.color = 190;
         }
 
-        stdout().execute(SetForegroundColor(Color::AnsiValue(self.color as u8))).ok();
+        let color = if self.color_depth == ColorDepth::Ansi16 {
+            ANSI16_CYCLE[self.color % ANSI16_CYCLE.len()]
+        } else {
+            Color::AnsiValue(self.color as u8)
+        };
+        stdout().execute(SetForegroundColor(color)).ok();
     }
 
     pub fn cursor_position(position: &Position) {
@@ -70,16 +121,19 @@ impl Terminal {
     pub fn flush() -> Result<(), std::io::Error> {
         stdout().flush()
     }
-    pub fn read(&mut self) -> Result<Event, std::io::Error> {
-        loop {
-            let event = read();
-
-            if let Ok(Event::Key(_)) = event {
-                self.cycle_colors();
-            }
+    /// Waits up to `timeout` for an event, so the editor gets to redraw
+    /// every now and then even when nobody types.
+    pub fn read(&mut self, timeout: Duration) -> Result<Option<Event>, std::io::Error> {
+        if !poll(timeout)? {
+            return Ok(None);
+        }
+        let event = read();
 
-            return event
+        if let Ok(Event::Key(_)) = event {
+            self.cycle_colors();
         }
+
+        event.map(Some)
     }
 
 
@@ -95,6 +149,12 @@ impl Terminal {
     pub fn set_colors(colors: Colors) {
         stdout().execute(SetColors(colors)).ok();
     }
+    pub fn set_foreground_color(color: Color) {
+        stdout().execute(SetForegroundColor(color)).ok();
+    }
+    pub fn set_attribute(attribute: Attribute) {
+        stdout().execute(SetAttribute(attribute)).ok();
+    }
     pub fn reset_colors() {
         stdout().execute(ResetColor).ok();
     }
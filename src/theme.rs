@@ -0,0 +1,329 @@
+use crate::config;
+use crate::highlighting;
+use crossterm::style::{Color, Colors};
+use std::env;
+use std::fs;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub fn detect() -> Self {
+        if let Ok(colorterm) = env::var("COLORTERM") {
+            if colorterm == "truecolor" || colorterm == "24bit" {
+                return ColorDepth::TrueColor;
+            }
+        }
+        match env::var("TERM") {
+            Ok(term) if term.ends_with("-direct") => ColorDepth::TrueColor,
+            Ok(term) if term.contains("256") => ColorDepth::Ansi256,
+            Ok(_) => ColorDepth::Ansi16,
+            // The Windows console sets no TERM but has understood RGB since Windows 10.
+            Err(_) if env::consts::OS == "windows" => ColorDepth::TrueColor,
+            Err(_) => ColorDepth::Ansi16,
+        }
+    }
+    pub fn from(name: &str) -> Option<Self> {
+        match name {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            _ => None,
+        }
+    }
+    pub fn convert(self, rgb: Rgb) -> Color {
+        match self {
+            ColorDepth::TrueColor => Color::Rgb {
+                r: rgb.r,
+                g: rgb.g,
+                b: rgb.b,
+            },
+            ColorDepth::Ansi256 => Color::AnsiValue(to_ansi256(rgb)),
+            ColorDepth::Ansi16 => to_ansi16(rgb),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+    /// Parses either `#rrggbb` or `r, g, b`.
+    pub fn parse(value: &str) -> Option<Self> {
+        if let Some(hex) = value.strip_prefix('#') {
+            if hex.len() != 6 || !hex.is_ascii() {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(Self::new(channel(0)?, channel(2)?, channel(4)?));
+        }
+        let mut channels = value.split(',').map(|c| c.trim().parse::<u8>());
+        let rgb = Self::new(
+            channels.next()?.ok()?,
+            channels.next()?.ok()?,
+            channels.next()?.ok()?,
+        );
+        if channels.next().is_some() {
+            return None;
+        }
+        Some(rgb)
+    }
+    fn distance(self, other: Self) -> u32 {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2) as u32;
+        d(self.r, other.r) + d(self.g, other.g) + d(self.b, other.b)
+    }
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    name: String,
+    depth: ColorDepth,
+    text: Rgb,
+    number: Rgb,
+    search_match: Rgb,
+    string: Rgb,
+    asteriscs: Rgb,
+    character: Rgb,
+    comment: Rgb,
+    primary_keywords: Rgb,
+    secondary_keywords: Rgb,
+    status_fg: Rgb,
+    status_bg: Rgb,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::pastel()
+    }
+}
+
+impl Theme {
+    fn pastel() -> Self {
+        Self {
+            name: String::from("pastel"),
+            depth: ColorDepth::TrueColor,
+            text: Rgb::new(255, 255, 255),
+            number: Rgb::new(192, 232, 127),
+            search_match: Rgb::new(38, 139, 210),
+            string: Rgb::new(211, 54, 130),
+            asteriscs: Rgb::new(232, 127, 145),
+            character: Rgb::new(108, 113, 196),
+            comment: Rgb::new(133, 153, 0),
+            primary_keywords: Rgb::new(247, 29, 153),
+            secondary_keywords: Rgb::new(42, 161, 152),
+            status_fg: Rgb::new(153, 1, 87),
+            status_bg: Rgb::new(252, 196, 228),
+        }
+    }
+    fn dark() -> Self {
+        Self {
+            name: String::from("dark"),
+            depth: ColorDepth::TrueColor,
+            text: Rgb::new(200, 190, 210),
+            number: Rgb::new(152, 195, 121),
+            search_match: Rgb::new(97, 175, 239),
+            string: Rgb::new(198, 120, 221),
+            asteriscs: Rgb::new(224, 108, 117),
+            character: Rgb::new(86, 182, 194),
+            comment: Rgb::new(110, 104, 128),
+            primary_keywords: Rgb::new(229, 102, 172),
+            secondary_keywords: Rgb::new(229, 192, 123),
+            status_fg: Rgb::new(60, 20, 70),
+            status_bg: Rgb::new(230, 200, 240),
+        }
+    }
+    fn high_contrast() -> Self {
+        Self {
+            name: String::from("high-contrast"),
+            depth: ColorDepth::TrueColor,
+            text: Rgb::new(255, 255, 255),
+            number: Rgb::new(0, 255, 0),
+            search_match: Rgb::new(0, 255, 255),
+            string: Rgb::new(255, 0, 255),
+            asteriscs: Rgb::new(255, 255, 0),
+            character: Rgb::new(255, 128, 0),
+            comment: Rgb::new(128, 128, 128),
+            primary_keywords: Rgb::new(255, 0, 0),
+            secondary_keywords: Rgb::new(0, 128, 255),
+            status_fg: Rgb::new(255, 255, 255),
+            status_bg: Rgb::new(0, 0, 0),
+        }
+    }
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "pastel" => Some(Self::pastel()),
+            "dark" => Some(Self::dark()),
+            "high-contrast" => Some(Self::high_contrast()),
+            _ => None,
+        }
+    }
+    /// Looks up a built-in theme, then `<config dir>/themes/<name>.theme`.
+    pub fn load(name: &str, depth: ColorDepth) -> Option<Self> {
+        let mut theme = if let Some(theme) = Self::builtin(name) {
+            theme
+        } else {
+            let path = config::dir()?.join("themes").join(format!("{}.theme", name));
+            let contents = fs::read_to_string(path).ok()?;
+            Self::parse(name, &contents)
+        };
+        theme.depth = depth;
+        Some(theme)
+    }
+    /// Reads a theme file. Every key is optional: colors start out from the
+    /// theme named by `base`, or from the pastel one.
+    pub fn parse(name: &str, contents: &str) -> Self {
+        let mut theme = Self::pastel();
+        for (key, value) in config::entries(contents) {
+            if key == "base" {
+                if let Some(base) = Self::builtin(value) {
+                    theme = base;
+                }
+                continue;
+            }
+            let rgb = if let Some(rgb) = Rgb::parse(value) {
+                rgb
+            } else {
+                continue;
+            };
+            match key {
+                "text" => theme.text = rgb,
+                "number" => theme.number = rgb,
+                "match" => theme.search_match = rgb,
+                "string" => theme.string = rgb,
+                "asteriscs" => theme.asteriscs = rgb,
+                "character" => theme.character = rgb,
+                "comment" => theme.comment = rgb,
+                "primary_keywords" => theme.primary_keywords = rgb,
+                "secondary_keywords" => theme.secondary_keywords = rgb,
+                "status_fg" => theme.status_fg = rgb,
+                "status_bg" => theme.status_bg = rgb,
+                _ => (),
+            }
+        }
+        theme.name = name.to_string();
+        theme
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn color(&self, hl_type: highlighting::Type) -> Color {
+        let rgb = match hl_type {
+            highlighting::Type::Number => self.number,
+            highlighting::Type::Match => self.search_match,
+            highlighting::Type::String => self.string,
+            highlighting::Type::Asteriscs => self.asteriscs,
+            highlighting::Type::Character => self.character,
+            highlighting::Type::Comment | highlighting::Type::MultilineComment => self.comment,
+            highlighting::Type::PrimaryKeywords => self.primary_keywords,
+            highlighting::Type::SecondaryKeywords => self.secondary_keywords,
+            highlighting::Type::None => self.text,
+        };
+        self.depth.convert(rgb)
+    }
+    pub fn status_colors(&self) -> Colors {
+        Colors::new(
+            self.depth.convert(self.status_fg),
+            self.depth.convert(self.status_bg),
+        )
+    }
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn to_ansi256(rgb: Rgb) -> u8 {
+    let nearest_level = |v: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| (i32::from(**level) - i32::from(v)).abs())
+            .map_or(0, |(index, _)| index)
+    };
+    let (r, g, b) = (nearest_level(rgb.r), nearest_level(rgb.g), nearest_level(rgb.b));
+    let cube = Rgb::new(CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+    let cube_index = 16 + 36 * r + 6 * g + b;
+
+    let average = (u32::from(rgb.r) + u32::from(rgb.g) + u32::from(rgb.b)) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23);
+    let gray_value = (8 + 10 * gray_step) as u8;
+    let gray = Rgb::new(gray_value, gray_value, gray_value);
+
+    if gray.distance(rgb) < cube.distance(rgb) {
+        232 + gray_step as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+const ANSI16: [(Color, Rgb); 16] = [
+    (Color::Black, Rgb::new(0, 0, 0)),
+    (Color::DarkRed, Rgb::new(128, 0, 0)),
+    (Color::DarkGreen, Rgb::new(0, 128, 0)),
+    (Color::DarkYellow, Rgb::new(128, 128, 0)),
+    (Color::DarkBlue, Rgb::new(0, 0, 128)),
+    (Color::DarkMagenta, Rgb::new(128, 0, 128)),
+    (Color::DarkCyan, Rgb::new(0, 128, 128)),
+    (Color::Grey, Rgb::new(192, 192, 192)),
+    (Color::DarkGrey, Rgb::new(128, 128, 128)),
+    (Color::Red, Rgb::new(255, 0, 0)),
+    (Color::Green, Rgb::new(0, 255, 0)),
+    (Color::Yellow, Rgb::new(255, 255, 0)),
+    (Color::Blue, Rgb::new(0, 0, 255)),
+    (Color::Magenta, Rgb::new(255, 0, 255)),
+    (Color::Cyan, Rgb::new(0, 255, 255)),
+    (Color::White, Rgb::new(255, 255, 255)),
+];
+
+fn to_ansi16(rgb: Rgb) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, candidate)| candidate.distance(rgb))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_to_ansi256() {
+        assert_eq!(to_ansi256(Rgb::new(0, 0, 0)), 16);
+        assert_eq!(to_ansi256(Rgb::new(255, 255, 255)), 231);
+        assert_eq!(to_ansi256(Rgb::new(255, 0, 0)), 196);
+        assert_eq!(to_ansi256(Rgb::new(128, 128, 128)), 244);
+        assert_eq!(to_ansi256(Rgb::new(247, 29, 153)), 198);
+    }
+
+    #[test]
+    fn test_to_ansi16() {
+        assert_eq!(to_ansi16(Rgb::new(250, 10, 10)), Color::Red);
+        assert_eq!(to_ansi16(Rgb::new(133, 153, 0)), Color::DarkYellow);
+        assert_eq!(to_ansi16(Rgb::new(20, 20, 20)), Color::Black);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Rgb::parse("#fcc4e4"), Some(Rgb::new(252, 196, 228)));
+        assert_eq!(Rgb::parse("252, 196, 228"), Some(Rgb::new(252, 196, 228)));
+        assert_eq!(Rgb::parse("252, 196"), None);
+        assert_eq!(Rgb::parse("#fcc4"), None);
+
+        let theme = Theme::parse("mine", "base = dark\n# comment\nstring = #ffffff\n");
+        assert_eq!(theme.string, Rgb::new(255, 255, 255));
+        assert_eq!(theme.number, Theme::dark().number);
+        assert_eq!(theme.name(), "mine");
+        let theme = Theme::parse("mine", "status_fg = #000000\n");
+        let colors = theme.status_colors();
+        assert_eq!(colors.foreground, Some(Color::Rgb { r: 0, g: 0, b: 0 }));
+    }
+}
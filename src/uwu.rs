@@ -0,0 +1,495 @@
+use crate::snippets::Snippets;
+use crate::spell::unuwu_candidates;
+use crate::substitutions::{is_word_boundary, Substitutions};
+use crate::Dictionary;
+use std::time::{SystemTime, UNIX_EPOCH};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// One in how many words starts with a stutter from [`Intensity::Medium`] on.
+const STUTTER_ODDS: u64 = 4;
+const SENTENCE_KAOMOJI: [&str; 6] = ["(◕‿◕✿)", "(｡♥‿♥｡)", "(ᵘﻌᵘ)", "owo", "(・`ω´・)", "(⁄ ⁄•⁄ω⁄•⁄ ⁄)"];
+
+/// What typing a character inserts, and how far the cursor moves afterwards.
+#[derive(PartialEq, Debug)]
+pub struct Expansion {
+    pub text: String,
+    pub cursor: usize,
+}
+
+impl Expansion {
+    fn new(text: &str, cursor: usize) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor,
+        }
+    }
+}
+
+/// The rules kyun applies to every typed character.
+pub fn transform(c: char) -> Expansion {
+    match c {
+        'l' | 'r' => Expansion::new("w", 1),
+        'L' | 'R' => Expansion::new("W", 1),
+        'U' => Expansion::new("UwU", 3),
+        'O' => Expansion::new("OwO", 3),
+        _ => Expansion {
+            text: c.to_string(),
+            cursor: 1,
+        },
+    }
+}
+
+/// What typing `word` leaves with the letter rules of [`transform`] alone.
+pub fn typed(word: &str) -> String {
+    word.chars().map(|c| transform(c).text).collect()
+}
+
+/// `text` without the stutters and "ny"s [`Intensity::Medium`] adds.
+pub fn without_intensity(text: &str) -> String {
+    let text = unstutter(text);
+    drop_ny(&text).unwrap_or(text)
+}
+
+/// How much uwu goes into typed text.
+#[derive(Default, PartialEq, Clone, Copy, Debug)]
+pub enum Intensity {
+    /// Just the letter swaps of [`transform`].
+    #[default]
+    Light,
+    /// Adds word-initial stutters ("h-hewwo") and "ny" after an n.
+    Medium,
+    /// Adds kaomoji after sentences, once a space follows, and escalates `!` into `!!1!`.
+    Maximum,
+}
+
+impl Intensity {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "light" | "1" => Some(Self::Light),
+            "medium" | "2" => Some(Self::Medium),
+            "maximum" | "max" | "3" => Some(Self::Maximum),
+            _ => None,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Light => "light",
+            Self::Medium => "medium",
+            Self::Maximum => "maximum",
+        }
+    }
+}
+
+/// A small xorshift generator, so the same seed gives the same stutters.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift never leaves zero.
+        Self(seed.max(1))
+    }
+    /// A seed that differs from run to run.
+    pub fn seed() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |time| time.as_nanos() as u64)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    pub fn one_in(&mut self, odds: u64) -> bool {
+        self.next_u64().is_multiple_of(odds)
+    }
+    pub fn pick<'a>(&mut self, choices: &[&'a str]) -> &'a str {
+        choices[(self.next_u64() % choices.len() as u64) as usize]
+    }
+}
+
+/// Applies autosnippets, then [`transform`] plus whatever the intensity
+/// adds, which depends on the text before the cursor and, for stutters and
+/// kaomoji, on chance.
+pub struct Uwuifier {
+    pub intensity: Intensity,
+    /// Whole words swapped once they are finished; none unless set.
+    pub substitutions: Substitutions,
+    /// Expands autosnippets such as `*` → `*notices *`; the bundled ones
+    /// unless set.
+    pub snippets: Snippets,
+    rng: Rng,
+}
+
+impl Default for Uwuifier {
+    fn default() -> Self {
+        Self::new(Intensity::Light, Rng::seed())
+    }
+}
+
+impl Uwuifier {
+    pub fn new(intensity: Intensity, seed: u64) -> Self {
+        Self {
+            intensity,
+            substitutions: Substitutions::default(),
+            snippets: Snippets::bundled(),
+            rng: Rng::new(seed),
+        }
+    }
+    /// What typing `c` inserts after `before`, the text left of the cursor.
+    pub fn transform(&mut self, before: &[char], c: char) -> Expansion {
+        if let Some((text, cursor)) = self.snippets.expand(c) {
+            return Expansion::new(&text, cursor);
+        }
+        let expansion = transform(c);
+        if self.intensity == Intensity::Light {
+            return expansion;
+        }
+        let previous = before.last().copied();
+        if self.intensity == Intensity::Maximum {
+            match c {
+                '!' => return Expansion::new("!!1!", 4),
+                // The kaomoji waits for the space after the sentence, so
+                // `main.rs` or `3.14` stay in one piece.
+                ' ' if ends_sentence(before) => {
+                    let text = format!(" {}{}", self.rng.pick(&SENTENCE_KAOMOJI), c);
+                    return Expansion::new(&text, text.graphemes(true).count());
+                }
+                _ => (),
+            }
+        }
+        if matches!(previous, Some('n') | Some('N')) && "aeiouAEIOU".contains(c) {
+            let y = if c.is_uppercase() { 'Y' } else { 'y' };
+            let text = format!("{}{}", y, expansion.text);
+            return Expansion::new(&text, expansion.cursor + 1);
+        }
+        let starts_word = c.is_alphabetic() && !previous.is_some_and(char::is_alphanumeric);
+        if starts_word && self.rng.one_in(STUTTER_ODDS) {
+            let first: String = expansion.text.chars().take(1).collect();
+            let text = format!("{}-{}", first, expansion.text);
+            return Expansion::new(&text, expansion.cursor + 2);
+        }
+        expansion
+    }
+}
+
+/// Whether `before` ends in a `.` or `?` right after a letter.
+fn ends_sentence(before: &[char]) -> bool {
+    matches!(before, [.., letter, '.' | '?'] if letter.is_alphabetic())
+}
+
+/// Produces what typing `text` into kyun would, cursor movements included.
+pub fn uwuify(text: &str) -> String {
+    uwuify_with(text, &mut Uwuifier::default())
+}
+
+/// Like [`uwuify`], at the intensity of `uwuifier` and with its word
+/// substitutions.
+pub fn uwuify_with(text: &str, uwuifier: &mut Uwuifier) -> String {
+    let mut result: Vec<char> = Vec::new();
+    let mut cursor: usize = 0;
+    for c in text.chars() {
+        let expansion = uwuifier.transform(&result[..cursor], c);
+        for (index, expanded) in expansion.text.chars().enumerate() {
+            result.insert(cursor + index, expanded);
+        }
+        let end = cursor;
+        cursor += expansion.cursor;
+        if is_word_boundary(c) {
+            cursor = substitute_word(&mut result, end, cursor, &uwuifier.substitutions);
+        }
+    }
+    result.into_iter().collect()
+}
+
+/// Swaps the word ending at `end` the way the editor does once a word is
+/// finished, and returns where `cursor` ends up.
+fn substitute_word(result: &mut Vec<char>, end: usize, cursor: usize, substitutions: &Substitutions) -> usize {
+    // Words never span whitespace, so there is no need to look further back.
+    let start = result[..end]
+        .iter()
+        .rposition(|c| c.is_whitespace())
+        .map_or(0, |index| index + 1);
+    let before: String = result[start..end].iter().collect();
+    let word = match before.unicode_word_indices().next_back() {
+        Some((word_start, word)) if word_start + word.len() == before.len() => word,
+        _ => return cursor,
+    };
+    match substitutions.get(word) {
+        Some(replacement) => {
+            let len = word.chars().count();
+            let new_len = replacement.chars().count();
+            result.splice(end - len..end, replacement.chars());
+            cursor + new_len - len
+        }
+        None => cursor,
+    }
+}
+
+/// How [`uwuify_text`] treats what it is given.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub struct Options {
+    /// Leaves Markdown code alone: fenced blocks and `inline spans`.
+    pub skip_code: bool,
+    /// Keeps `\r\n` line endings instead of writing `\n` throughout.
+    pub keep_line_endings: bool,
+}
+
+/// Uwuifies a whole text the way [`uwuify_with`] does, with `options` on top.
+pub fn uwuify_text(text: &str, options: &Options, uwuifier: &mut Uwuifier) -> String {
+    let crlf: Vec<bool> = text.split('\n').map(|line| line.ends_with('\r')).collect();
+    let text = text.replace("\r\n", "\n");
+    let result = if options.skip_code {
+        uwuify_prose(&text, uwuifier)
+    } else {
+        uwuify_with(&text, uwuifier)
+    };
+    if !options.keep_line_endings {
+        return result;
+    }
+    // Typing never adds or removes line breaks, so they still line up.
+    let mut lines = result.split('\n').zip(crlf).peekable();
+    let mut with_endings = String::new();
+    while let Some((line, crlf)) = lines.next() {
+        with_endings.push_str(line);
+        if lines.peek().is_some() {
+            with_endings.push_str(if crlf { "\r\n" } else { "\n" });
+        }
+    }
+    with_endings
+}
+
+/// Uwuifies everything outside of Markdown code.
+fn uwuify_prose(text: &str, uwuifier: &mut Uwuifier) -> String {
+    let mut result = String::new();
+    let mut prose = String::new();
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let is_fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        if in_fence || is_fence {
+            result.push_str(&uwuify_with(&prose, uwuifier));
+            prose.clear();
+            result.push_str(line);
+            in_fence ^= is_fence;
+            continue;
+        }
+        let mut rest = line;
+        while let Some(start) = rest.find('`') {
+            // A backtick without a partner on the same line is just text.
+            let end = match rest[start + 1..].find('`') {
+                Some(end) => start + end + 2,
+                None => break,
+            };
+            prose.push_str(&rest[..start]);
+            result.push_str(&uwuify_with(&prose, uwuifier));
+            prose.clear();
+            result.push_str(&rest[start..end]);
+            rest = &rest[end..];
+        }
+        prose.push_str(rest);
+    }
+    result.push_str(&uwuify_with(&prose, uwuifier));
+    result
+}
+
+/// Undoes what typing did, as far as that can be told: `*notices ` goes,
+/// sentence kaomoji and stutters go, `!!1!` calms down to `!`, `UwU` and
+/// `OwO` shrink back to `U` and `O`, and each word with a `w` or an added
+/// "ny" becomes the one spelling the dictionary knows. Also returns the
+/// grapheme ranges of the words with a `w` but no such spelling or several.
+pub fn deuwuify_line(line: &str, dictionary: &Dictionary) -> (String, Vec<(usize, usize)>) {
+    let mut line = line.replace("*notices ", "*").replace("!!1!", "!");
+    for kaomoji in SENTENCE_KAOMOJI.iter() {
+        for terminator in ['.', '?'] {
+            let added = format!("{} {}", terminator, kaomoji);
+            if line.ends_with(&added) {
+                line.truncate(line.len() - kaomoji.len() - 1);
+            }
+            line = line.replace(&format!("{} ", added), &format!("{} ", terminator));
+        }
+    }
+    let line = unstutter(&line).replace("UwU", "U").replace("OwO", "O");
+    let mut result = String::new();
+    let mut ambiguous = Vec::new();
+    let mut end = 0;
+    for (start, word) in line.unicode_word_indices() {
+        result.push_str(&line[end..start]);
+        end = start + word.len();
+        match original_spelling(word, dictionary) {
+            Some(spelling) => result.push_str(&spelling),
+            None => {
+                let first = result.graphemes(true).count();
+                result.push_str(word);
+                ambiguous.push((first, first + word.graphemes(true).count()));
+            }
+        }
+    }
+    result.push_str(&line[end..]);
+    (result, ambiguous)
+}
+
+/// The one spelling `word` had before typing, as far as the dictionary
+/// can tell, or `None` for a word with a `w` and no such spelling or
+/// several. Words without a `w` or an added "ny" stay as they are.
+pub(crate) fn original_spelling(word: &str, dictionary: &Dictionary) -> Option<String> {
+    let has_w = word.contains(['w', 'W']);
+    let without_ny = drop_ny(word);
+    if word.chars().count() < 2 || (!has_w && without_ny.is_none()) {
+        return Some(word.to_string());
+    }
+    // The word as it is, and without the "ny" Medium may have added.
+    let candidates: Vec<String> = Some(word.to_string())
+        .into_iter()
+        .chain(without_ny)
+        .flat_map(|spelling| {
+            if spelling.contains(['w', 'W']) {
+                unuwu_candidates(&spelling)
+            } else {
+                vec![spelling]
+            }
+        })
+        .collect();
+    // Spellings only known with a suffix taken off come second.
+    let mut spellings: Vec<&String> = candidates
+        .iter()
+        .filter(|spelling| dictionary.contains_exactly(spelling))
+        .collect();
+    if spellings.is_empty() {
+        spellings = candidates
+            .iter()
+            .filter(|spelling| dictionary.contains(spelling))
+            .collect();
+    }
+    match spellings.as_slice() {
+        [spelling] => Some(spelling.to_string()),
+        [] if !has_w => Some(word.to_string()),
+        _ => None,
+    }
+}
+
+/// `line` without the stutters [`Intensity::Medium`] puts at word starts,
+/// so "h-hewwo" is "hewwo" again.
+fn unstutter(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        let starts_word = index == 0 || !chars[index - 1].is_alphanumeric();
+        let stutters = chars.get(index + 1) == Some(&'-') && chars.get(index + 2) == Some(&c);
+        if c.is_alphabetic() && starts_word && stutters {
+            index += 2;
+            continue;
+        }
+        result.push(c);
+        index += 1;
+    }
+    result
+}
+
+/// `word` without the y of each "ny" before a vowel, if it has any.
+fn drop_ny(word: &str) -> Option<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let mut result = String::new();
+    let mut found = false;
+    for (index, c) in chars.iter().enumerate() {
+        let after_n = index > 0 && matches!(chars[index - 1], 'n' | 'N');
+        let before_vowel = chars.get(index + 1).is_some_and(|next| "aeiouAEIOU".contains(*next));
+        if matches!(c, 'y' | 'Y') && after_n && before_vowel {
+            found = true;
+            continue;
+        }
+        result.push(*c);
+    }
+    if found {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test_super {
+    use super::*;
+
+    #[test]
+    fn test_uwuify() {
+        assert_eq!(uwuify("Hello world"), "Hewwo wowwd");
+        assert_eq!(uwuify("Ok, Uh"), "OwOk, UwUh");
+        assert_eq!(uwuify("*hug"), "*notices hug*");
+        assert_eq!(uwuify("*hug* ok"), "*notices hug*notices  ok**");
+    }
+
+    #[test]
+    fn test_substitutions() {
+        let mut uwuifier = Uwuifier {
+            substitutions: Substitutions::parse("the = da\ncute = kyute\nlittle = widdle\n"),
+            ..Uwuifier::default()
+        };
+        // What typing the same keys into the editor leaves behind.
+        assert_eq!(
+            uwuify_with("the cute, little (the) cutest thing", &mut uwuifier),
+            "da kyute, widdle (da) cutest thing"
+        );
+        let options = Options::default();
+        assert_eq!(uwuify_text("Little cat\nthe\n", &options, &mut uwuifier), "Widdle cat\nda\n");
+    }
+
+    #[test]
+    fn test_uwuify_text() {
+        let text = "Hello `world`\r\n```\nlet r = 1;\n```\nreally\r\n";
+        let options = Options {
+            skip_code: true,
+            keep_line_endings: true,
+        };
+        assert_eq!(
+            uwuify_text(text, &options, &mut Uwuifier::default()),
+            "Hewwo `world`\r\n```\nlet r = 1;\n```\nweawwy\r\n"
+        );
+        assert_eq!(
+            uwuify_text(text, &Options::default(), &mut Uwuifier::default()),
+            "Hewwo `wowwd`\n```\nwet w = 1;\n```\nweawwy\n"
+        );
+    }
+
+    #[test]
+    fn test_deuwuify_line() {
+        let dictionary = Dictionary::from_words("hello world roll wight light right we");
+        assert_eq!(
+            deuwuify_line("Hewwo wowwd, OwOk? *notices hug*", &dictionary),
+            ("Hello world, Ok? *hug*".to_string(), vec![])
+        );
+        assert_eq!(
+            deuwuify_line("a wight, we", &dictionary),
+            ("a wight, we".to_string(), vec![(2, 7)])
+        );
+        assert_eq!(
+            deuwuify_line("UwUh zwxq", &dictionary),
+            ("Uh zwxq".to_string(), vec![(3, 7)])
+        );
+        let dictionary = Dictionary::from_words("no one knows how nice a hello is anyone yes really");
+        assert_eq!(
+            deuwuify_line("nyo o-onye knyows how nyice a h-hewwo i-is, anyone", &dictionary),
+            ("no one knows how nice a hello is, anyone".to_string(), vec![])
+        );
+        assert_eq!(
+            deuwuify_line("Yes!!1! W-Weawwy. owo nyo? (ᵘﻌᵘ) Weawwy. (◕‿◕✿)", &dictionary),
+            ("Yes! Really. no? Really.".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn test_intensity() {
+        let text = "no one knows how nice a hello is";
+        let mut medium = Uwuifier::new(Intensity::Medium, 7);
+        assert_eq!(uwuify_with(text, &mut medium), "nyo o-onye knyows how nyice a hewwo i-is");
+        let mut maximum = Uwuifier::new(Intensity::Maximum, 7);
+        assert_eq!(uwuify_with("Yes! Really. 3.14", &mut maximum), "Yes!!1! W-Weawwy. owo 3.14");
+        let mut maximum = Uwuifier::new(Intensity::Maximum, 7);
+        assert_eq!(uwuify_with("see main.rs, e.g", &mut maximum), "see m-main.ws, e.g");
+        let mut light = Uwuifier::new(Intensity::Light, 7);
+        assert_eq!(uwuify_with(text, &mut light), "no one knows how nice a hewwo is");
+        assert_eq!(Intensity::from_name("Max"), Some(Intensity::Maximum));
+    }
+}